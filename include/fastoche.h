@@ -0,0 +1,91 @@
+/*
+ * C interface of the fastoche library (libfastoche.so).
+ *
+ * Functions return FASTOCHE_OK or an error code, and fastoche_last_error()
+ * then describes the failure of the calling thread.
+ */
+
+#ifndef FASTOCHE_H
+#define FASTOCHE_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+#define FASTOCHE_ABI_VERSION 1
+
+#define FASTOCHE_OK 0
+#define FASTOCHE_NULL_POINTER 1
+#define FASTOCHE_INVALID_ARGUMENT 2
+#define FASTOCHE_ERROR 3
+
+/* Main fields of the fastoche report */
+typedef struct fastoche_metrics {
+    uint64_t cumul;
+    uint64_t number;
+    uint64_t min_size;
+    uint64_t max_size;
+    uint64_t avg_size;
+    uint64_t aun;
+    uint64_t number_n;
+    double percent_n;
+    uint64_t number_gc;
+    double percent_gc;
+    uint64_t number_masked;
+    double percent_masked;
+    uint64_t n50;
+    uint64_t l50;
+    uint64_t n80;
+    uint64_t l80;
+    uint64_t n90;
+    uint64_t l90;
+    uint64_t ng50;
+    uint64_t lg50;
+    uint64_t ng80;
+    uint64_t lg80;
+    uint64_t ng90;
+    uint64_t lg90;
+    uint64_t mean_quality;
+} fastoche_metrics;
+
+/* Accumulates records, released with fastoche_builder_free() */
+typedef struct fastoche_builder fastoche_builder;
+
+/* FASTOCHE_ABI_VERSION of the loaded library, which must match the one of
+ * this header */
+int fastoche_abi_version(void);
+
+/* Message of the last error of the calling thread, or NULL. Valid until the
+ * next failing call on this thread. Panics are reported there only, without
+ * printing to stderr. */
+const char *fastoche_last_error(void);
+
+/* A genome_size of 0 disables the NG/LG fields */
+fastoche_builder *fastoche_builder_new(int64_t genome_size, uint8_t qual_offset);
+void fastoche_builder_free(fastoche_builder *builder);
+
+/* qual may be NULL, otherwise it holds seq_len quality characters */
+int fastoche_builder_add_record(fastoche_builder *builder, const uint8_t *seq,
+                                size_t seq_len, const uint8_t *qual);
+
+/* Adds the records of a Fastx, SAM/BAM, GFA or 2bit file of at least
+ * min_size bases */
+int fastoche_builder_add_file(fastoche_builder *builder, const char *path,
+                              size_t min_size);
+
+/* Returns FASTOCHE_INVALID_ARGUMENT when no records were added. The builder
+ * can still be used afterwards. */
+int fastoche_builder_compute(const fastoche_builder *builder,
+                             fastoche_metrics *metrics);
+
+int fastoche_stats_file(const char *path, size_t min_size, int64_t genome_size,
+                        fastoche_metrics *metrics);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* FASTOCHE_H */
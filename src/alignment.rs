@@ -0,0 +1,371 @@
+use std::io::{BufRead, BufReader, Read};
+
+const BAM_MAGIC: &[u8; 4] = b"BAM\x01";
+const BAM_SEQ_CODES: &[u8; 16] = b"=ACMGRSVTWYHKDBN";
+
+const FLAG_REVERSE: u16 = 0x10;
+const FLAG_SECONDARY: u16 = 0x100;
+const FLAG_SUPPLEMENTARY: u16 = 0x800;
+
+/// Primary record of a SAM/BAM file, with qualities encoded with the quality
+/// offset of the reader and the sequence restored to the orientation of the
+/// sequencer.
+#[derive(Debug, Default)]
+pub struct AlignmentRecord {
+    pub id: Vec<u8>,
+    pub seq: Vec<u8>,
+    pub qual: Option<Vec<u8>>,
+    /// PacBio predicted read accuracy
+    pub rq: Option<f32>,
+    /// PacBio number of passes
+    pub np: Option<u32>,
+    /// Bytes of the record in the decompressed file, with the header and
+    /// the skipped records before it
+    pub input_bytes: u64,
+}
+
+enum Format {
+    Bam(Box<dyn Read>),
+    Sam(Box<dyn BufRead>),
+}
+
+/// Reads the sequences of unaligned (or aligned) SAM and BAM files, skipping
+/// secondary and supplementary records so that each read is counted once.
+pub struct AlignmentReader {
+    format: Format,
+    record: AlignmentRecord,
+    line: String,
+    // Bytes read since the last record returned
+    pending_bytes: u64,
+    /// Offset of the qualities of the records, 33 by default as in SAM files
+    pub qual_offset: u8,
+    pub secondary: usize,
+    pub supplementary: usize,
+}
+
+impl AlignmentReader {
+    /// Reader of a decompressed BAM stream
+    pub fn bam<R: Read + 'static>(reader: R) -> Self {
+        let mut reader: Box<dyn Read> = Box::new(reader);
+        let header_bytes = read_bam_header(&mut reader);
+        let mut bam_reader = Self::new(Format::Bam(reader));
+        bam_reader.pending_bytes = header_bytes;
+        bam_reader
+    }
+
+    pub fn sam<R: Read + 'static>(reader: R) -> Self {
+        Self::new(Format::Sam(Box::new(BufReader::new(reader))))
+    }
+
+    fn new(format: Format) -> Self {
+        AlignmentReader {
+            format,
+            record: AlignmentRecord::default(),
+            line: String::new(),
+            pending_bytes: 0,
+            qual_offset: 33,
+            secondary: 0,
+            supplementary: 0,
+        }
+    }
+
+    // Not an Iterator, as the record borrows the reader's buffer
+    pub fn next_record(&mut self) -> Option<&AlignmentRecord> {
+        loop {
+            let (flag, bytes) = match &mut self.format {
+                Format::Bam(reader) => read_bam_record(reader, &mut self.record, self.qual_offset)?,
+                Format::Sam(reader) => {
+                    read_sam_record(reader, &mut self.line, &mut self.record, self.qual_offset)?
+                }
+            };
+            self.pending_bytes += bytes;
+
+            if flag & FLAG_SECONDARY != 0 {
+                self.secondary += 1;
+            } else if flag & FLAG_SUPPLEMENTARY != 0 {
+                self.supplementary += 1;
+            } else {
+                if flag & FLAG_REVERSE != 0 {
+                    self.record.reverse_complement();
+                }
+                self.record.input_bytes = std::mem::take(&mut self.pending_bytes);
+                return Some(&self.record);
+            }
+        }
+    }
+}
+
+impl AlignmentRecord {
+    fn reverse_complement(&mut self) {
+        self.seq.reverse();
+        for c in self.seq.iter_mut() {
+            *c = match *c {
+                b'A' => b'T',
+                b'T' => b'A',
+                b'C' => b'G',
+                b'G' => b'C',
+                b'a' => b't',
+                b't' => b'a',
+                b'c' => b'g',
+                b'g' => b'c',
+                c => c,
+            };
+        }
+        if let Some(qual) = self.qual.as_mut() {
+            qual.reverse();
+        }
+    }
+}
+
+/// Skips the BAM header and returns its size
+fn read_bam_header(reader: &mut Box<dyn Read>) -> u64 {
+    let mut magic = [0u8; 4];
+    match reader.read_exact(&mut magic) {
+        Ok(()) => {}
+        Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => panic!("Truncated BAM header"),
+        Err(e) => panic!("Failed to read BAM header: {e}"),
+    }
+    assert!(&magic == BAM_MAGIC, "File is not a valid BAM file");
+
+    let l_text = read_i32(reader).expect("Truncated BAM header") as u64;
+    skip(reader, l_text);
+    let mut bytes = 12 + l_text;
+
+    let n_ref = read_i32(reader).expect("Truncated BAM header");
+    for _ in 0..n_ref {
+        let l_name = read_i32(reader).expect("Truncated BAM header") as u64;
+        // Reference name followed by its length
+        skip(reader, l_name + 4);
+        bytes += l_name + 8;
+    }
+
+    bytes
+}
+
+/// Decodes the next BAM record in place and returns its flag and size
+fn read_bam_record(
+    reader: &mut Box<dyn Read>,
+    record: &mut AlignmentRecord,
+    qual_offset: u8,
+) -> Option<(u16, u64)> {
+    let block_size = read_i32(reader)?;
+    // Fixed-length fields of the record
+    if block_size < 32 {
+        panic!("Invalid BAM record: block of {block_size} bytes");
+    }
+    let mut block = vec![0u8; block_size as usize];
+    reader
+        .read_exact(&mut block)
+        .unwrap_or_else(|e| panic!("Truncated BAM record: {e}"));
+
+    let l_read_name = block[8] as usize;
+    let n_cigar_op = u16::from_le_bytes([block[12], block[13]]) as usize;
+    let flag = u16::from_le_bytes([block[14], block[15]]);
+    let l_seq = i32::from_le_bytes([block[16], block[17], block[18], block[19]]);
+
+    let name_start = 32;
+    let seq_start = name_start + l_read_name + 4 * n_cigar_op;
+    let qual_start = seq_start + (l_seq.max(0) as usize).div_ceil(2);
+    let tags_start = qual_start + l_seq.max(0) as usize;
+    // The read name holds at least its NUL terminator
+    if l_read_name == 0 || l_seq < 0 || tags_start > block.len() {
+        panic!(
+            "Invalid BAM record: a {l_read_name}-byte name, {n_cigar_op} CIGAR operations and {l_seq} bases do not fit in {block_size} bytes"
+        );
+    }
+    let l_seq = l_seq as usize;
+
+    // Read name is NUL-terminated
+    record.id.clear();
+    record
+        .id
+        .extend_from_slice(&block[name_start..name_start + l_read_name - 1]);
+
+    record.seq.clear();
+    record.seq.extend((0..l_seq).map(|i| {
+        let byte = block[seq_start + i / 2];
+        let code = if i % 2 == 0 { byte >> 4 } else { byte & 0x0f };
+        BAM_SEQ_CODES[code as usize]
+    }));
+
+    // Qualities are raw Phred scores, 0xff when missing
+    let qual = &block[qual_start..tags_start];
+    record.qual = if l_seq == 0 || qual[0] == 0xff {
+        None
+    } else {
+        Some(qual.iter().map(|q| q.saturating_add(qual_offset)).collect())
+    };
+
+    read_bam_tags(&block[tags_start..], record);
+
+    Some((flag, 4 + block_size as u64))
+}
+
+/// Extracts the PacBio rq and np tags from the BAM auxiliary fields
+fn read_bam_tags(mut tags: &[u8], record: &mut AlignmentRecord) {
+    record.rq = None;
+    record.np = None;
+
+    while tags.len() >= 3 {
+        let name = [tags[0], tags[1]];
+        let value_type = tags[2];
+        tags = &tags[3..];
+
+        let size = match value_type {
+            b'B' if tags.len() < 5 => panic!("Invalid BAM record: truncated tags"),
+            b'A' | b'c' | b'C' => 1,
+            b's' | b'S' => 2,
+            b'i' | b'I' | b'f' => 4,
+            b'Z' | b'H' => tags
+                .iter()
+                .position(|c| *c == 0)
+                .map_or(tags.len(), |p| p + 1),
+            b'B' => {
+                let element_size = match tags[0] {
+                    b'c' | b'C' => 1,
+                    b's' | b'S' => 2,
+                    _ => 4,
+                };
+                let count = u32::from_le_bytes([tags[1], tags[2], tags[3], tags[4]]) as usize;
+                5 + element_size * count
+            }
+            _ => panic!("Invalid BAM tag type: {}", value_type as char),
+        };
+        if size > tags.len() {
+            panic!("Invalid BAM record: truncated tags");
+        }
+        let value = &tags[..size];
+
+        match &name {
+            b"rq" if value_type == b'f' => {
+                record.rq = Some(f32::from_le_bytes([value[0], value[1], value[2], value[3]]));
+            }
+            b"np" => record.np = bam_integer(value_type, value),
+            _ => {}
+        }
+
+        tags = &tags[size..];
+    }
+}
+
+fn bam_integer(value_type: u8, value: &[u8]) -> Option<u32> {
+    match value_type {
+        b'c' => Some(value[0] as i8 as u32),
+        b'C' => Some(value[0] as u32),
+        b's' => Some(i16::from_le_bytes([value[0], value[1]]) as u32),
+        b'S' => Some(u16::from_le_bytes([value[0], value[1]]) as u32),
+        b'i' => Some(i32::from_le_bytes([value[0], value[1], value[2], value[3]]) as u32),
+        b'I' => Some(u32::from_le_bytes([value[0], value[1], value[2], value[3]])),
+        _ => None,
+    }
+}
+
+/// Parses the next SAM record in place and returns its flag and size, with
+/// the header lines before it
+fn read_sam_record(
+    reader: &mut Box<dyn BufRead>,
+    line: &mut String,
+    record: &mut AlignmentRecord,
+    qual_offset: u8,
+) -> Option<(u16, u64)> {
+    let mut bytes = 0;
+    loop {
+        line.clear();
+        let read = reader
+            .read_line(line)
+            .unwrap_or_else(|e| panic!("Failed to read SAM file: {e}"));
+        if read == 0 {
+            return None;
+        }
+        bytes += read as u64;
+        if !line.starts_with('@') && !line.trim_end().is_empty() {
+            break;
+        }
+    }
+
+    let columns = line
+        .trim_end_matches(['\n', '\r'])
+        .split('\t')
+        .collect::<Vec<&str>>();
+    assert!(columns.len() >= 11, "Invalid SAM record: {line}");
+    let flag = columns[1]
+        .parse::<u16>()
+        .unwrap_or_else(|_| panic!("Invalid SAM flag: {}", columns[1]));
+
+    record.id.clear();
+    record.id.extend_from_slice(columns[0].as_bytes());
+
+    record.seq.clear();
+    if columns[9] != "*" {
+        record.seq.extend_from_slice(columns[9].as_bytes());
+    }
+
+    // SAM qualities are always Phred+33
+    record.qual = match columns[10] {
+        "*" => None,
+        qual if qual_offset == 33 => Some(qual.as_bytes().to_vec()),
+        qual => Some(
+            qual.bytes()
+                .map(|q| q.saturating_sub(33).saturating_add(qual_offset))
+                .collect(),
+        ),
+    };
+
+    record.rq = None;
+    record.np = None;
+    for tag in &columns[11..] {
+        if let Some(rq) = tag.strip_prefix("rq:f:") {
+            record.rq = rq.parse().ok();
+        } else if let Some(np) = tag.strip_prefix("np:i:") {
+            record.np = np.parse().ok();
+        }
+    }
+
+    Some((flag, bytes))
+}
+
+/// Reads a little-endian i32, returns None at the end of the file
+fn read_i32(reader: &mut Box<dyn Read>) -> Option<i32> {
+    let mut bytes = [0u8; 4];
+    match reader.read_exact(&mut bytes) {
+        Ok(()) => Some(i32::from_le_bytes(bytes)),
+        Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => None,
+        Err(e) => panic!("Failed to read BAM file: {e}"),
+    }
+}
+
+/// Skips `n` bytes of the BAM header
+fn skip(reader: &mut Box<dyn Read>, n: u64) {
+    let skipped = std::io::copy(&mut reader.take(n), &mut std::io::sink())
+        .unwrap_or_else(|e| panic!("Failed to read BAM header: {e}"));
+    assert!(skipped == n, "Truncated BAM header");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[should_panic(expected = "Invalid BAM record")]
+    fn test_invalid_bam_record() {
+        // 32-byte block claiming a 10-byte name and 100 bases
+        let mut bytes = 32i32.to_le_bytes().to_vec();
+        let mut block = [0u8; 32];
+        block[8] = 10;
+        block[16] = 100;
+        bytes.extend_from_slice(&block);
+        let mut reader: Box<dyn Read> = Box::new(std::io::Cursor::new(bytes));
+        read_bam_record(&mut reader, &mut AlignmentRecord::default(), 33);
+    }
+
+    #[test]
+    #[should_panic(expected = "Truncated BAM header")]
+    fn test_truncated_bam_header() {
+        // Header text of 100 bytes cut off after 10 bytes
+        let mut bytes = BAM_MAGIC.to_vec();
+        bytes.extend_from_slice(&100i32.to_le_bytes());
+        bytes.extend_from_slice(&[b'@'; 10]);
+        let mut reader: Box<dyn Read> = Box::new(std::io::Cursor::new(bytes));
+        read_bam_header(&mut reader);
+    }
+}
@@ -0,0 +1,170 @@
+use serde::{Deserialize, Serialize};
+
+const FNV_OFFSET: u128 = 0x6c62272e07bb014262b821756295c58d;
+const FNV_PRIME: u128 = 0x0000000001000000000000000000013b;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChecksumOptions {
+    pub qualities: bool,
+    pub canonical: bool,
+}
+
+/// Order-independent digest of the records of a file.
+///
+/// Each record is hashed on its own (FNV-1a, 128 bits) and the record hashes
+/// are added together, so shuffling the records does not change the result
+/// while duplicated records still do. Only the records added to the metrics
+/// are hashed, so records removed by `--min-size` or the filters are not.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Checksum {
+    pub options: ChecksumOptions,
+    sum: u128,
+}
+
+impl Checksum {
+    pub fn new(options: ChecksumOptions) -> Self {
+        Checksum { options, sum: 0 }
+    }
+
+    /// Adds the records of another checksum, computed with the same options
+    pub fn merge(&mut self, other: &Checksum) {
+        assert!(
+            self.options == other.options,
+            "Cannot merge checksums computed with different options"
+        );
+        self.sum = self.sum.wrapping_add(other.sum);
+    }
+
+    pub fn add_record(&mut self, seq: &[u8], qual: Option<&[u8]>) {
+        let qual = if self.options.qualities { qual } else { None };
+
+        let hash = if self.options.canonical {
+            let upper = seq.to_ascii_uppercase();
+            let revcomp = reverse_complement(&upper);
+            if revcomp < upper {
+                let rev_qual = qual.map(|q| q.iter().rev().copied().collect::<Vec<u8>>());
+                hash_record(&revcomp, rev_qual.as_deref())
+            } else {
+                hash_record(&upper, qual)
+            }
+        } else {
+            hash_record(seq, qual)
+        };
+
+        self.sum = self.sum.wrapping_add(hash);
+    }
+
+    pub fn digest(&self) -> String {
+        format!("{:032x}", self.sum)
+    }
+}
+
+fn hash_record(seq: &[u8], qual: Option<&[u8]>) -> u128 {
+    let mut hash = fnv1a(FNV_OFFSET, seq);
+    if let Some(qual) = qual {
+        // Separator so that moving bytes between sequence and qualities changes the hash
+        hash = fnv1a(hash, &[0xff]);
+        hash = fnv1a(hash, qual);
+    }
+    hash
+}
+
+fn fnv1a(mut hash: u128, bytes: &[u8]) -> u128 {
+    for b in bytes {
+        hash ^= *b as u128;
+        hash = hash.wrapping_mul(FNV_PRIME);
+    }
+    hash
+}
+
+/// Reverse complement of an uppercase sequence. Characters without a
+/// complement (N, S, W, U...) are kept, so that it is an involution.
+fn reverse_complement(seq: &[u8]) -> Vec<u8> {
+    seq.iter()
+        .rev()
+        .map(|c| match c {
+            b'A' => b'T',
+            b'T' => b'A',
+            b'C' => b'G',
+            b'G' => b'C',
+            b'R' => b'Y',
+            b'Y' => b'R',
+            b'K' => b'M',
+            b'M' => b'K',
+            b'B' => b'V',
+            b'V' => b'B',
+            b'D' => b'H',
+            b'H' => b'D',
+            c => *c,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn digest(options: ChecksumOptions, records: &[(&[u8], &[u8])]) -> String {
+        let mut checksum = Checksum::new(options);
+        for (seq, qual) in records {
+            checksum.add_record(seq, Some(qual));
+        }
+        checksum.digest()
+    }
+
+    #[test]
+    fn test_order_independence() {
+        let options = ChecksumOptions::default();
+        let first: (&[u8], &[u8]) = (b"ACGTTG", b"IIIIII");
+        let second: (&[u8], &[u8]) = (b"GGCA", b"####");
+        assert_eq!(
+            digest(options, &[first, second]),
+            digest(options, &[second, first])
+        );
+        assert_ne!(
+            digest(options, &[first, second]),
+            digest(options, &[first, second, second])
+        );
+    }
+
+    #[test]
+    fn test_canonical_checksum() {
+        let canonical = ChecksumOptions {
+            qualities: false,
+            canonical: true,
+        };
+        let forward: &[(&[u8], &[u8])] = &[(b"AACGTRN", b"")];
+        let reverse: &[(&[u8], &[u8])] = &[(b"NYACGTT", b"")];
+        let lowercase: &[(&[u8], &[u8])] = &[(b"aacgtrn", b"")];
+        assert_eq!(digest(canonical, forward), digest(canonical, reverse));
+        assert_eq!(digest(canonical, forward), digest(canonical, lowercase));
+
+        let options = ChecksumOptions::default();
+        assert_ne!(digest(options, forward), digest(options, reverse));
+        assert_ne!(digest(options, forward), digest(options, lowercase));
+    }
+
+    #[test]
+    fn test_quality_checksum() {
+        let records: &[(&[u8], &[u8])] = &[(b"ACGT", b"IIII")];
+        let other_qualities: &[(&[u8], &[u8])] = &[(b"ACGT", b"II#I")];
+        let options = ChecksumOptions::default();
+        assert_eq!(digest(options, records), digest(options, other_qualities));
+
+        let with_qualities = ChecksumOptions {
+            qualities: true,
+            canonical: false,
+        };
+        assert_ne!(
+            digest(with_qualities, records),
+            digest(with_qualities, other_qualities)
+        );
+        assert_ne!(digest(with_qualities, records), digest(options, records));
+    }
+
+    #[test]
+    fn test_reverse_complement_involution() {
+        let seq = b"ACGTURYKMBVDHNSW-*";
+        assert_eq!(reverse_complement(&reverse_complement(seq)), seq);
+    }
+}
@@ -0,0 +1,778 @@
+use crate::checksum::ChecksumOptions;
+use crate::composition::non_nucleotide_message;
+use crate::filter::failed_filter;
+use crate::histogram::length_histogram;
+use crate::inputs::{
+    expand_inputs, parse_input, read_fofn, rename_inputs, rename_inputs_from_file, Input,
+};
+use crate::metrics::{Metrics, MetricsBuilder};
+use crate::parser::{
+    build, build_with_per_seq, parse, read_records, Config, GroupBy, NonNucleotide,
+};
+use crate::report::{parse_output_format, write_comparison, write_histogram, write_report};
+use crate::sample::SampleMode;
+use crate::state::{merge_states, write_state};
+use crate::writer::{create_output, write_record, Output};
+use clap::error::ErrorKind;
+use clap::{CommandFactory, Parser, Subcommand};
+use std::io::{BufWriter, Write};
+use std::path::PathBuf;
+
+#[derive(Parser)]
+#[command(author="Benjamin Istace",
+    about="Computes statistics about Fastx files that are gzipped or not",
+    long_about=None,
+    args_conflicts_with_subcommands = true,
+    subcommand_negates_reqs = true
+)]
+struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    #[command(flatten)]
+    stats: StatsArgs,
+}
+
+#[derive(clap::Args)]
+struct StatsArgs {
+    #[arg(
+        short,
+        required_unless_present_any = ["paired", "fofn"],
+        value_parser = parse_input,
+        help = "Fastx, SAM, BAM, GFA or 2bit files to process. Fastx, SAM and GFA files can be gzipped. Can be specified multiple times if you need to compute metrics on several files. Use name=file_1,file_2 to pool several files in a single column. Directories and glob patterns are expanded to the supported files they hold."
+    )]
+    files: Vec<Input>,
+
+    #[arg(
+        long,
+        conflicts_with_all = ["rename", "rename_file"],
+        help = "File of filenames: one path per line, optionally followed by a tab and the name to display"
+    )]
+    fofn: Option<PathBuf>,
+
+    #[arg(
+        short,
+        long,
+        default_value_t = 0,
+        help = "Sequences shorter than this number will not be processed."
+    )]
+    min_size: usize,
+
+    #[arg(
+        long,
+        conflicts_with_all = ["paired", "interleaved"],
+        help = "Sequences longer than this number will not be processed."
+    )]
+    max_size: Option<usize>,
+
+    #[arg(
+        long,
+        conflicts_with_all = ["paired", "interleaved", "fai"],
+        help = "Reads with a lower mean quality will not be processed."
+    )]
+    min_qual: Option<f64>,
+
+    #[arg(
+        long,
+        conflicts_with_all = ["paired", "interleaved", "fai"],
+        help = "Sequences with a higher fraction of N will not be processed."
+    )]
+    max_n_frac: Option<f64>,
+
+    #[arg(
+        long,
+        conflicts_with_all = ["paired", "interleaved", "fai"],
+        help = "Sequences with a lower GC percentage will not be processed."
+    )]
+    min_gc: Option<f64>,
+
+    #[arg(
+        long,
+        conflicts_with_all = ["paired", "interleaved", "fai"],
+        help = "Sequences with a higher GC percentage will not be processed."
+    )]
+    max_gc: Option<f64>,
+
+    #[arg(
+        short,
+        long,
+        conflicts_with_all = ["paired", "interleaved", "fai", "group_by"],
+        help = "Write the records passing the filters to this Fasta or Fastq file (gzipped with a .gz extension), and report the metrics before and after filtering"
+    )]
+    output: Option<PathBuf>,
+
+    #[arg(
+        long,
+        value_parser = parse_fraction,
+        conflicts_with_all = ["sample_reads", "paired", "interleaved", "fai", "group_by"],
+        help = "Compute the metrics on this fraction of the reads, picked at random"
+    )]
+    sample_fraction: Option<f64>,
+
+    #[arg(
+        long,
+        conflicts_with_all = ["paired", "interleaved", "fai", "group_by"],
+        help = "Compute the metrics on this number of reads, picked uniformly at random (kept in memory until the end of the input)"
+    )]
+    sample_reads: Option<usize>,
+
+    #[arg(
+        long,
+        default_value_t = 0,
+        help = "Seed of the random sampling, which gives the same sample for the same seed"
+    )]
+    seed: u64,
+
+    #[arg(
+        long,
+        value_parser = parse_count,
+        conflicts_with_all = ["sample_fraction", "sample_reads", "head_bytes", "paired", "interleaved", "fai", "group_by"],
+        help = "Only read the first records of each input, and estimate its total number of reads and bases from the bytes read"
+    )]
+    head: Option<usize>,
+
+    #[arg(
+        long,
+        value_parser = clap::value_parser!(u64).range(1..),
+        conflicts_with_all = ["sample_fraction", "sample_reads", "paired", "interleaved", "fai", "group_by"],
+        help = "Only read about this number of bytes (compressed for gzipped files) at the start of each input, and estimate its total number of reads and bases"
+    )]
+    head_bytes: Option<u64>,
+
+    #[arg(
+        short,
+        default_value_t = 0,
+        help = "Estimated genome size to compute NGX metrics (in bases)."
+    )]
+    genome_size: i64,
+
+    #[arg(
+        short,
+        long,
+        default_value_t = 33,
+        help = "Phred quality offset (usually 33 or 64)"
+    )]
+    quality: u8,
+
+    #[arg(
+        short,
+        long,
+        default_value_t = false,
+        help = "Activate parsable mode (csv format with metrics as rows)"
+    )]
+    csv: bool,
+
+    #[arg(
+        short,
+        long,
+        default_value_t = false,
+        conflicts_with = "csv",
+        help = "Activate parsable mode (csv format with metrics as columns)"
+    )]
+    parsable: bool,
+
+    #[arg(
+        long,
+        conflicts_with = "csv",
+        help = "Comma-separated list of metrics to output in parsable mode (implies --parsable)"
+    )]
+    output_format: Option<String>,
+
+    #[arg(
+        long,
+        requires = "output_format",
+        default_value_t = false,
+        help = "(--output-format only) Do not print a header"
+    )]
+    no_header: bool,
+
+    #[arg(
+        long,
+        conflicts_with_all = ["paired", "interleaved"],
+        help = "Activate per sequence metrics mode. Provide a path to a file to store the metrics. WARNING: does not work for multiple input files."
+    )]
+    per_seq: Option<PathBuf>,
+
+    #[arg(
+        short,
+        long,
+        conflicts_with = "paired",
+        help = "Use these names instead of inferring them. Format name_1,name_2,name_n"
+    )]
+    rename: Option<String>,
+
+    #[arg(
+        long,
+        conflicts_with_all = ["rename", "paired"],
+        help = "Use the names of a tab-separated file holding a path and a name on each line"
+    )]
+    rename_file: Option<PathBuf>,
+
+    #[arg(
+        long,
+        default_value_t = false,
+        help = "Compute an order-independent checksum of the sequences of each file. Sequences removed by --min-size or the other filters are left out."
+    )]
+    checksum: bool,
+
+    #[arg(
+        long,
+        requires = "checksum",
+        default_value_t = false,
+        help = "(--checksum only) Include the qualities in the checksum"
+    )]
+    checksum_qual: bool,
+
+    #[arg(
+        long,
+        requires = "checksum",
+        default_value_t = false,
+        help = "(--checksum only) Ignore case and strand when computing the checksum"
+    )]
+    checksum_canonical: bool,
+
+    #[arg(
+        long,
+        num_args = 2,
+        value_names = ["R1", "R2"],
+        help = "Paired-end files. Read ids are checked pairwise and metrics are reported for each mate."
+    )]
+    paired: Option<Vec<PathBuf>>,
+
+    #[arg(
+        long,
+        default_value_t = false,
+        help = "Input files are interleaved paired-end files. Metrics are reported for each mate."
+    )]
+    interleaved: bool,
+
+    #[arg(
+        long,
+        default_value_t = false,
+        help = "Parse Nanopore read headers to report the yield per hour and the channel activity"
+    )]
+    nanopore: bool,
+
+    #[arg(
+        long,
+        default_value_t = false,
+        help = "Parse Illumina (Casava 1.8+) read headers to report the most frequent index sequences"
+    )]
+    illumina: bool,
+
+    #[arg(
+        long,
+        value_enum,
+        conflicts_with_all = ["interleaved", "paired"],
+        help = "Split the metrics of each input by a read header field"
+    )]
+    group_by: Option<GroupBy>,
+
+    #[arg(
+        long,
+        default_value_t = false,
+        conflicts_with_all = ["paired", "interleaved", "group_by", "checksum", "per_seq"],
+        help = "Only compute the length metrics, from the samtools faidx index (file.fai) of each Fasta file"
+    )]
+    fai: bool,
+
+    #[arg(
+        long,
+        default_value_t = false,
+        conflicts_with_all = ["fai", "csv", "parsable", "output_format"],
+        help = "Print the count of each base, IUPAC ambiguity code and unexpected character"
+    )]
+    composition: bool,
+
+    #[arg(
+        long,
+        value_enum,
+        conflicts_with = "fai",
+        help = "Warn or fail when sequences hold non-nucleotide characters (protein or corrupted files)"
+    )]
+    non_nucleotide: Option<NonNucleotide>,
+
+    #[arg(
+        long,
+        default_value_t = false,
+        conflicts_with_all = ["fai", "paired", "interleaved"],
+        help = "Input files hold proteins. Detected from the alphabet when not given."
+    )]
+    protein: bool,
+
+    #[arg(
+        long,
+        help = "Save the raw counts of each input (JSON, gzipped with a .gz extension) to combine them later with `fastoche merge`"
+    )]
+    save_state: Option<PathBuf>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Compute the metrics of each input (the default without subcommand)
+    Stats(Box<StatsArgs>),
+    /// Write the length, GC, soft-masked percentage and mean quality of each sequence
+    Seqs(SeqsArgs),
+    /// Print the length histogram of each input
+    Hist(HistArgs),
+    /// Print the metrics of two inputs side by side, with their differences
+    Compare(CompareArgs),
+    /// Check that inputs can be read and hold no empty, duplicated or non-nucleotide sequences
+    Validate(ValidateArgs),
+    /// Write the records of the inputs that pass the length, quality, N and GC filters
+    Filter(FilterArgs),
+    /// Combine the states saved with --save-state on chunks of the same inputs into exact metrics
+    Merge(MergeArgs),
+}
+
+/// Inputs of the focused subcommands
+#[derive(clap::Args)]
+struct InputArgs {
+    #[arg(
+        short,
+        required = true,
+        value_parser = parse_input,
+        help = "Fastx, SAM, BAM, GFA or 2bit files to process. Fastx, SAM and GFA files can be gzipped. Use name=file_1,file_2 to pool several files. Directories and glob patterns are expanded to the supported files they hold."
+    )]
+    files: Vec<Input>,
+
+    #[arg(
+        short,
+        long,
+        default_value_t = 33,
+        help = "Phred quality offset (usually 33 or 64)"
+    )]
+    quality: u8,
+}
+
+#[derive(clap::Args)]
+struct SeqsArgs {
+    #[command(flatten)]
+    inputs: InputArgs,
+
+    #[arg(
+        short,
+        long,
+        default_value_t = 0,
+        help = "Sequences shorter than this number will not be processed."
+    )]
+    min_size: usize,
+}
+
+#[derive(clap::Args)]
+struct HistArgs {
+    #[command(flatten)]
+    inputs: InputArgs,
+
+    #[arg(
+        short,
+        long,
+        default_value_t = 0,
+        help = "Sequences shorter than this number will not be processed."
+    )]
+    min_size: usize,
+
+    #[arg(short, long, default_value_t = 20, help = "Number of bins")]
+    bins: usize,
+}
+
+#[derive(clap::Args)]
+struct CompareArgs {
+    #[command(flatten)]
+    inputs: InputArgs,
+
+    #[arg(
+        short,
+        long,
+        default_value_t = 0,
+        help = "Sequences shorter than this number will not be processed."
+    )]
+    min_size: usize,
+
+    #[arg(
+        short,
+        default_value_t = 0,
+        help = "Estimated genome size to compute NGX metrics (in bases)."
+    )]
+    genome_size: i64,
+}
+
+#[derive(clap::Args)]
+struct ValidateArgs {
+    #[command(flatten)]
+    inputs: InputArgs,
+}
+
+#[derive(clap::Args)]
+struct FilterArgs {
+    #[command(flatten)]
+    inputs: InputArgs,
+
+    #[arg(
+        short,
+        long,
+        default_value_t = 0,
+        help = "Sequences shorter than this number are not written."
+    )]
+    min_size: usize,
+
+    #[arg(long, help = "Sequences longer than this number are not written.")]
+    max_size: Option<usize>,
+
+    #[arg(long, help = "Reads with a lower mean quality are not written.")]
+    min_qual: Option<f64>,
+
+    #[arg(long, help = "Sequences with a higher fraction of N are not written.")]
+    max_n_frac: Option<f64>,
+
+    #[arg(long, help = "Sequences with a lower GC percentage are not written.")]
+    min_gc: Option<f64>,
+
+    #[arg(long, help = "Sequences with a higher GC percentage are not written.")]
+    max_gc: Option<f64>,
+
+    #[arg(
+        short,
+        long,
+        help = "Output Fasta or Fastq file, gzipped with a .gz extension. Records are written to stdout otherwise."
+    )]
+    output: Option<PathBuf>,
+
+    #[arg(
+        long,
+        value_parser = parse_fraction,
+        conflicts_with = "sample_reads",
+        help = "Only write this fraction of the reads, picked at random"
+    )]
+    sample_fraction: Option<f64>,
+
+    #[arg(
+        long,
+        help = "Only write this number of reads, picked uniformly at random (kept in memory until the end of the input)"
+    )]
+    sample_reads: Option<usize>,
+
+    #[arg(
+        long,
+        default_value_t = 0,
+        help = "Seed of the random sampling, which gives the same sample for the same seed"
+    )]
+    seed: u64,
+}
+
+#[derive(clap::Args)]
+struct MergeArgs {
+    #[arg(
+        required = true,
+        help = "State files saved with --save-state, holding the same inputs in the same order"
+    )]
+    states: Vec<PathBuf>,
+
+    #[arg(
+        short,
+        long,
+        default_value_t = false,
+        help = "Activate parsable mode (csv format with metrics as rows)"
+    )]
+    csv: bool,
+
+    #[arg(
+        short,
+        long,
+        default_value_t = false,
+        conflicts_with = "csv",
+        help = "Activate parsable mode (csv format with metrics as columns)"
+    )]
+    parsable: bool,
+
+    #[arg(
+        long,
+        conflicts_with = "csv",
+        help = "Comma-separated list of metrics to output in parsable mode (implies --parsable)"
+    )]
+    output_format: Option<String>,
+
+    #[arg(
+        long,
+        requires = "output_format",
+        default_value_t = false,
+        help = "(--output-format only) Do not print a header"
+    )]
+    no_header: bool,
+
+    #[arg(long, help = "Save the merged state, to combine it again later")]
+    save_state: Option<PathBuf>,
+}
+
+impl InputArgs {
+    fn config(&self, min_size: usize) -> Config {
+        Config {
+            min_size,
+            qual_offset: self.quality,
+            ..Default::default()
+        }
+    }
+}
+
+fn seqs(args: SeqsArgs) {
+    let config = args.inputs.config(args.min_size);
+    let inputs = expand_inputs(args.inputs.files);
+
+    let mut stdout = BufWriter::new(std::io::stdout());
+    writeln!(
+        stdout,
+        "id\tlength\tpercent_gc\tmean_quality\tpercent_masked"
+    )
+    .unwrap_or_else(|e| panic!("Failed to write sequences: {e}"));
+    let mut per_seq_writer: Option<Box<dyn Write>> = Some(Box::new(stdout));
+    build_with_per_seq(&inputs, &config, &mut per_seq_writer);
+    if let Some(writer) = per_seq_writer.as_mut() {
+        writer
+            .flush()
+            .unwrap_or_else(|e| panic!("Failed to write sequences: {e}"));
+    }
+}
+
+fn hist(args: HistArgs) {
+    let config = args.inputs.config(args.min_size);
+    let inputs = expand_inputs(args.inputs.files);
+
+    let mut stdout = std::io::stdout().lock();
+    for builder in build(&inputs, &config) {
+        let (edges, counts) = length_histogram(&builder.seq_sizes, args.bins);
+        write_histogram(&mut stdout, &builder.filename, &edges, &counts)
+            .unwrap_or_else(|e| panic!("Failed to write histogram: {e}"));
+    }
+}
+
+fn compare(args: CompareArgs) {
+    let config = Config {
+        genome_size: args.genome_size,
+        checksum: Some(ChecksumOptions::default()),
+        ..args.inputs.config(args.min_size)
+    };
+    let inputs = expand_inputs(args.inputs.files);
+    if inputs.len() != 2 {
+        Args::command()
+            .error(
+                ErrorKind::WrongNumberOfValues,
+                format!("compare needs 2 inputs, {} were given", inputs.len()),
+            )
+            .exit();
+    }
+
+    let metrics_vec = parse(&inputs, &config);
+    write_comparison(
+        &mut std::io::stdout().lock(),
+        &metrics_vec[0],
+        &metrics_vec[1],
+    )
+    .unwrap_or_else(|e| panic!("Failed to write comparison: {e}"));
+}
+
+fn validate(args: ValidateArgs) {
+    let config = args.inputs.config(0);
+    let inputs = expand_inputs(args.inputs.files);
+
+    // Parsing errors are reported with the other problems of the input, so
+    // the panic hook is silenced while validating
+    let hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(|_| {}));
+    let validations = inputs
+        .iter()
+        .map(|input| crate::validate::validate(input, &config))
+        .collect::<Vec<_>>();
+    std::panic::set_hook(hook);
+
+    let mut valid = true;
+    for validation in &validations {
+        if validation.is_valid() {
+            println!(
+                "{}: OK ({} records)",
+                validation.filename, validation.records
+            );
+        } else {
+            valid = false;
+            println!(
+                "{}: INVALID ({} records)",
+                validation.filename, validation.records
+            );
+            for error in &validation.errors {
+                println!("  {error}");
+            }
+        }
+    }
+
+    if !valid {
+        std::process::exit(1);
+    }
+}
+
+fn filter(args: FilterArgs) {
+    let config = Config {
+        max_size: args.max_size,
+        min_qual: args.min_qual,
+        max_n_frac: args.max_n_frac,
+        min_gc: args.min_gc,
+        max_gc: args.max_gc,
+        sample: sample_mode(args.sample_fraction, args.sample_reads),
+        seed: args.seed,
+        ..args.inputs.config(args.min_size)
+    };
+    let inputs = expand_inputs(args.inputs.files);
+
+    let mut output = match &args.output {
+        Some(path) => create_output(path),
+        None => Output::stdout(),
+    };
+    for input in &inputs {
+        read_records(&input.paths, &config, |record| {
+            // GFA segments without sequence cannot be written
+            if failed_filter(record, &config).is_none() && record.seq().len() == record.len() {
+                write_record(&mut output, record)
+                    .unwrap_or_else(|e| panic!("Failed to write records: {e}"));
+            }
+        });
+    }
+    output.finish();
+}
+
+fn sample_mode(fraction: Option<f64>, reads: Option<usize>) -> Option<SampleMode> {
+    fraction
+        .map(SampleMode::Fraction)
+        .or(reads.map(SampleMode::Reads))
+}
+
+fn parse_fraction(value: &str) -> Result<f64, String> {
+    match value.parse::<f64>() {
+        Ok(fraction) if fraction > 0.0 && fraction <= 1.0 => Ok(fraction),
+        _ => Err(format!("{value} is not a fraction between 0 and 1")),
+    }
+}
+
+fn parse_count(value: &str) -> Result<usize, String> {
+    match value.parse::<usize>() {
+        Ok(count) if count > 0 => Ok(count),
+        _ => Err(format!("{value} is not a positive number")),
+    }
+}
+
+fn merge(args: MergeArgs) {
+    let builders = merge_states(&args.states)
+        .unwrap_or_else(|e| Args::command().error(ErrorKind::InvalidValue, e).exit());
+    if let Some(path) = &args.save_state {
+        write_state(path, &builders);
+    }
+
+    let config = Config {
+        csv: args.csv,
+        parsable: args.parsable || args.output_format.is_some(),
+        output_fields: parse_output_format(&args.output_format),
+        no_header: args.no_header,
+        ..Default::default()
+    };
+    let metrics_vec = builders
+        .into_iter()
+        .map(MetricsBuilder::compute)
+        .collect::<Vec<Metrics>>();
+
+    write_report(&mut std::io::stdout().lock(), &metrics_vec, &config)
+        .unwrap_or_else(|e| panic!("Failed to write report: {e}"));
+}
+
+/// Runs the command line with the arguments of the process
+pub fn run() {
+    let args = Args::parse();
+    match args.command {
+        None => stats(args.stats),
+        Some(Command::Stats(stats_args)) => stats(*stats_args),
+        Some(Command::Seqs(seqs_args)) => seqs(seqs_args),
+        Some(Command::Hist(hist_args)) => hist(hist_args),
+        Some(Command::Compare(compare_args)) => compare(compare_args),
+        Some(Command::Validate(validate_args)) => validate(validate_args),
+        Some(Command::Filter(filter_args)) => filter(filter_args),
+        Some(Command::Merge(merge_args)) => merge(merge_args),
+    }
+}
+
+fn stats(args: StatsArgs) {
+    let output_fields = parse_output_format(&args.output_format);
+    let checksum = args.checksum.then_some(ChecksumOptions {
+        qualities: args.checksum_qual,
+        canonical: args.checksum_canonical,
+    });
+    let config = Config {
+        min_size: args.min_size,
+        max_size: args.max_size,
+        min_qual: args.min_qual,
+        max_n_frac: args.max_n_frac,
+        min_gc: args.min_gc,
+        max_gc: args.max_gc,
+        sample: sample_mode(args.sample_fraction, args.sample_reads)
+            .or(args.head.map(SampleMode::Head))
+            .or(args.head_bytes.map(SampleMode::HeadBytes)),
+        seed: args.seed,
+        output: args.output,
+        genome_size: args.genome_size,
+        qual_offset: args.quality,
+        parsable: args.parsable || output_fields.is_some(),
+        csv: args.csv,
+        per_seq: args.per_seq,
+        output_fields,
+        no_header: args.no_header,
+        checksum,
+        paired: args.paired.map(|p| (p[0].clone(), p[1].clone())),
+        interleaved: args.interleaved,
+        nanopore: args.nanopore,
+        illumina: args.illumina,
+        group_by: args.group_by,
+        fai: args.fai,
+        composition: args.composition,
+        non_nucleotide: args.non_nucleotide,
+        protein: args.protein,
+    };
+    let mut inputs = args.files;
+    if let Some(fofn) = &args.fofn {
+        match read_fofn(fofn) {
+            Ok(fofn_inputs) => inputs.extend(fofn_inputs),
+            Err(e) => Args::command().error(ErrorKind::InvalidValue, e).exit(),
+        }
+    }
+    let mut inputs = expand_inputs(inputs);
+    let renamed = match (&args.rename, &args.rename_file) {
+        (Some(rename), _) => rename_inputs(&mut inputs, rename),
+        (_, Some(rename_file)) => rename_inputs_from_file(&mut inputs, rename_file),
+        _ => Ok(()),
+    };
+    if let Err(e) = renamed {
+        Args::command().error(ErrorKind::InvalidValue, e).exit();
+    }
+
+    let builders = build(&inputs, &config);
+    if let Some(path) = &args.save_state {
+        write_state(path, &builders);
+    }
+    let metrics_vec = builders
+        .into_iter()
+        .map(MetricsBuilder::compute)
+        .collect::<Vec<Metrics>>();
+
+    if let Some(non_nucleotide) = config.non_nucleotide {
+        let mut failed = false;
+        for message in metrics_vec.iter().filter_map(non_nucleotide_message) {
+            match non_nucleotide {
+                NonNucleotide::Warn => eprintln!("Warning: {message}"),
+                NonNucleotide::Fail => {
+                    eprintln!("Error: {message}");
+                    failed = true;
+                }
+            }
+        }
+        if failed {
+            std::process::exit(1);
+        }
+    }
+
+    write_report(&mut std::io::stdout().lock(), &metrics_vec, &config)
+        .unwrap_or_else(|e| panic!("Failed to write report: {e}"));
+}
@@ -0,0 +1,123 @@
+use crate::metrics::Metrics;
+
+const BASES: &[u8] = b"ACGTN";
+/// IUPAC nucleotide ambiguity codes, besides N. U (RNA) is not one of them
+/// and is reported as unexpected.
+const AMBIGUITY_CODES: &[u8] = b"RYSWKMBDHV";
+
+/// Character counts of the sequences, case-insensitive, split between the
+/// bases, the IUPAC ambiguity codes and any other (unexpected) byte
+#[derive(Debug, Default)]
+pub struct Composition {
+    pub bases: Vec<(u8, usize)>,
+    pub ambiguity: Vec<(u8, usize)>,
+    pub unexpected: Vec<(u8, usize)>,
+}
+
+impl Composition {
+    pub fn from_counts(counts: &[usize; 256]) -> Self {
+        let count = |c: u8| {
+            if c.is_ascii_uppercase() {
+                counts[c as usize] + counts[c.to_ascii_lowercase() as usize]
+            } else {
+                counts[c as usize]
+            }
+        };
+
+        let mut composition = Composition {
+            bases: BASES.iter().map(|c| (*c, count(*c))).collect(),
+            ..Default::default()
+        };
+
+        for c in 0..=255u8 {
+            let count = count(c);
+            if c.is_ascii_lowercase() || BASES.contains(&c) || count == 0 {
+                continue;
+            } else if AMBIGUITY_CODES.contains(&c) {
+                composition.ambiguity.push((c, count));
+            } else {
+                composition.unexpected.push((c, count));
+            }
+        }
+
+        composition
+    }
+
+    pub fn unexpected_count(&self) -> usize {
+        self.unexpected.iter().map(|(_, count)| count).sum()
+    }
+}
+
+/// Printable name of a sequence byte, escaped when it is not visible
+pub fn char_name(c: u8) -> String {
+    if c.is_ascii_graphic() {
+        (c as char).to_string()
+    } else {
+        format!("0x{c:02x}")
+    }
+}
+
+/// Message listing the non-nucleotide characters of an input, if any. Protein
+/// inputs, detected or declared, are expected to hold them.
+pub fn non_nucleotide_message(metrics: &Metrics) -> Option<String> {
+    if !metrics.has_composition || metrics.protein {
+        return None;
+    }
+
+    let composition = Composition::from_counts(&metrics.nucleotide_counts);
+    if composition.unexpected.is_empty() {
+        return None;
+    }
+
+    let characters = composition
+        .unexpected
+        .iter()
+        .map(|(c, _)| char_name(*c))
+        .collect::<Vec<String>>()
+        .join(" ");
+    Some(format!(
+        "{} holds {} non-nucleotide characters ({characters}), it may be a protein or corrupted file",
+        metrics.filename,
+        composition.unexpected_count()
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metrics::MetricsBuilder;
+
+    #[test]
+    fn test_composition() {
+        let mut counts = [0; 256];
+        for c in b"ACGTNacgtnRYyU*-.1" {
+            counts[*c as usize] += 1;
+        }
+
+        let composition = Composition::from_counts(&counts);
+        assert_eq!(
+            composition.bases,
+            vec![(b'A', 2), (b'C', 2), (b'G', 2), (b'T', 2), (b'N', 2)]
+        );
+        assert_eq!(composition.ambiguity, vec![(b'R', 1), (b'Y', 2)]);
+        assert_eq!(
+            composition.unexpected,
+            vec![(b'*', 1), (b'-', 1), (b'.', 1), (b'1', 1), (b'U', 1)]
+        );
+        assert_eq!(composition.unexpected_count(), 5);
+    }
+
+    #[test]
+    fn test_non_nucleotide_message() {
+        let mut builder = MetricsBuilder::new("reads.fa", 0, None);
+        builder.add_record(b"ACGT*ACGT", None);
+        let message = non_nucleotide_message(&builder.compute());
+        assert!(message.is_some_and(|m| m.contains("1 non-nucleotide characters (*)")));
+
+        let mut builder = MetricsBuilder::new("proteins.faa", 0, None);
+        builder.add_record(b"MKVLAAGIVALLLAAGCSSSKETPQ*", None);
+        let metrics = builder.compute();
+        assert!(metrics.protein);
+        assert_eq!(non_nucleotide_message(&metrics), None);
+    }
+}
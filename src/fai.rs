@@ -0,0 +1,87 @@
+use crate::filter::Filter;
+use crate::metrics::MetricsBuilder;
+use crate::parser::Config;
+use std::path::{Path, PathBuf};
+
+/// Accumulates the lengths of Fasta files from their samtools faidx index
+/// alone. The composition metrics are not available.
+pub fn build_fai_stats(
+    file_paths: &[PathBuf],
+    config: &Config,
+    name: Option<String>,
+) -> MetricsBuilder {
+    // Named after the Fasta file, even when the index is given
+    let path = file_paths[0].to_str().unwrap();
+    let path = path.strip_suffix(".fai").unwrap_or(path);
+    let mut builder = MetricsBuilder::new(path, config.genome_size, name);
+    builder.has_composition = false;
+
+    for file_path in file_paths {
+        let fai_path = fai_path(file_path);
+        let content = std::fs::read_to_string(&fai_path)
+            .unwrap_or_else(|e| panic!("Failed to read index {fai_path:?}: {e}"));
+
+        for line in content.lines().filter(|line| !line.is_empty()) {
+            let len = line
+                .split('\t')
+                .nth(1)
+                .and_then(|len| len.parse::<usize>().ok())
+                .unwrap_or_else(|| panic!("Invalid line in {fai_path:?}: {line}"));
+
+            if len < config.min_size {
+                builder.add_removed(Filter::MinSize, len);
+            } else if config.max_size.is_some_and(|max| len > max) {
+                builder.add_removed(Filter::MaxSize, len);
+            } else {
+                builder.add_length(len);
+            }
+        }
+    }
+
+    builder
+}
+
+fn fai_path(file_path: &Path) -> PathBuf {
+    if file_path.extension().is_some_and(|ext| ext == "fai") {
+        return file_path.to_path_buf();
+    }
+
+    let fai_path = PathBuf::from(format!("{}.fai", file_path.display()));
+    assert!(
+        fai_path.exists(),
+        "No index found for {file_path:?}, create it with samtools faidx"
+    );
+    fai_path
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fai_stats() {
+        let config = Config::default();
+        let metrics = build_fai_stats(
+            &[PathBuf::from("test_inputs/assembly.fasta")],
+            &config,
+            None,
+        )
+        .compute();
+        assert_eq!(metrics.filename, "assembly");
+        assert_eq!(metrics.number, 5);
+        assert_eq!(metrics.cumul, 1_610_000);
+        assert_eq!(metrics.n50, 500_000);
+        assert_eq!(metrics.l50, 2);
+        assert_eq!(metrics.min_size, 10_000);
+        assert!(!metrics.has_composition);
+
+        let metrics = build_fai_stats(
+            &[PathBuf::from("test_inputs/assembly.fasta.fai")],
+            &config,
+            None,
+        )
+        .compute();
+        assert_eq!(metrics.filename, "assembly");
+        assert_eq!(metrics.number, 5);
+    }
+}
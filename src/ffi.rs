@@ -0,0 +1,322 @@
+//! C ABI of the library, declared in include/fastoche.h. Functions return a
+//! status code instead of panicking, and `fastoche_last_error()` describes the
+//! last failure of the calling thread.
+
+use crate::catch_quietly;
+use crate::metrics::{Metrics, MetricsBuilder};
+use crate::parser::{build_stats, Config};
+use std::cell::RefCell;
+use std::ffi::{c_char, c_int, CStr, CString};
+use std::path::PathBuf;
+
+/// Version of the C interface, bumped when include/fastoche.h changes
+pub const FASTOCHE_ABI_VERSION: c_int = 1;
+
+pub const FASTOCHE_OK: c_int = 0;
+pub const FASTOCHE_NULL_POINTER: c_int = 1;
+pub const FASTOCHE_INVALID_ARGUMENT: c_int = 2;
+pub const FASTOCHE_ERROR: c_int = 3;
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+fn set_last_error(message: &str) {
+    let message = CString::new(message.replace('\0', " ")).unwrap();
+    LAST_ERROR.with(|last_error| *last_error.borrow_mut() = Some(message));
+}
+
+/// Runs `f`, turning its error or panic into a status code. Panics are only
+/// reported by `fastoche_last_error()`.
+fn status(f: impl FnOnce() -> Result<(), (c_int, String)>) -> c_int {
+    match catch_quietly(f) {
+        Ok(Ok(())) => FASTOCHE_OK,
+        Ok(Err((code, message))) => {
+            set_last_error(&message);
+            code
+        }
+        Err(message) => {
+            set_last_error(&message);
+            FASTOCHE_ERROR
+        }
+    }
+}
+
+/// Plain copy of the main fields of `Metrics`
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy)]
+pub struct FastocheMetrics {
+    pub cumul: u64,
+    pub number: u64,
+    pub min_size: u64,
+    pub max_size: u64,
+    pub avg_size: u64,
+    pub aun: u64,
+    pub number_n: u64,
+    pub percent_n: f64,
+    pub number_gc: u64,
+    pub percent_gc: f64,
+    pub number_masked: u64,
+    pub percent_masked: f64,
+    pub n50: u64,
+    pub l50: u64,
+    pub n80: u64,
+    pub l80: u64,
+    pub n90: u64,
+    pub l90: u64,
+    pub ng50: u64,
+    pub lg50: u64,
+    pub ng80: u64,
+    pub lg80: u64,
+    pub ng90: u64,
+    pub lg90: u64,
+    pub mean_quality: u64,
+}
+
+impl From<&Metrics> for FastocheMetrics {
+    fn from(metrics: &Metrics) -> Self {
+        FastocheMetrics {
+            cumul: metrics.cumul as u64,
+            number: metrics.number as u64,
+            min_size: metrics.min_size as u64,
+            max_size: metrics.max_size as u64,
+            avg_size: metrics.avg_size as u64,
+            aun: metrics.aun as u64,
+            number_n: metrics.number_n as u64,
+            percent_n: metrics.percent_n,
+            number_gc: metrics.number_gc as u64,
+            percent_gc: metrics.percent_gc,
+            number_masked: metrics.number_masked as u64,
+            percent_masked: metrics.percent_masked,
+            n50: metrics.n50 as u64,
+            l50: metrics.l50 as u64,
+            n80: metrics.n80 as u64,
+            l80: metrics.l80 as u64,
+            n90: metrics.n90 as u64,
+            l90: metrics.l90 as u64,
+            ng50: metrics.ng50 as u64,
+            lg50: metrics.lg50 as u64,
+            ng80: metrics.ng80 as u64,
+            lg80: metrics.lg80 as u64,
+            ng90: metrics.ng90 as u64,
+            lg90: metrics.lg90 as u64,
+            mean_quality: metrics.mean_quality as u64,
+        }
+    }
+}
+
+/// Version of the C interface of the loaded library, to compare with the
+/// `FASTOCHE_ABI_VERSION` of the header a program was built with
+#[no_mangle]
+pub extern "C" fn fastoche_abi_version() -> c_int {
+    FASTOCHE_ABI_VERSION
+}
+
+/// Message of the last error of the calling thread, or NULL. The pointer is
+/// valid until the next failing call on this thread.
+#[no_mangle]
+pub extern "C" fn fastoche_last_error() -> *const c_char {
+    LAST_ERROR.with(|last_error| {
+        last_error
+            .borrow()
+            .as_ref()
+            .map_or(std::ptr::null(), |message| message.as_ptr())
+    })
+}
+
+/// Creates a builder, to be released with `fastoche_builder_free`
+#[no_mangle]
+pub extern "C" fn fastoche_builder_new(genome_size: i64, qual_offset: u8) -> *mut MetricsBuilder {
+    let mut builder = MetricsBuilder::new("", genome_size, Some(String::new()));
+    builder.qual_offset = qual_offset;
+    Box::into_raw(Box::new(builder))
+}
+
+/// # Safety
+///
+/// `builder` must come from `fastoche_builder_new` and not be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn fastoche_builder_free(builder: *mut MetricsBuilder) {
+    if !builder.is_null() {
+        drop(Box::from_raw(builder));
+    }
+}
+
+/// Adds a record. `qual` may be NULL, otherwise it holds `seq_len` qualities.
+///
+/// # Safety
+///
+/// `builder` must come from `fastoche_builder_new`, `seq` must point to
+/// `seq_len` bytes and `qual`, when not NULL, to `seq_len` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn fastoche_builder_add_record(
+    builder: *mut MetricsBuilder,
+    seq: *const u8,
+    seq_len: usize,
+    qual: *const u8,
+) -> c_int {
+    status(|| {
+        let builder = builder.as_mut().ok_or(null_pointer("builder"))?;
+        if seq.is_null() {
+            return Err(null_pointer("seq"));
+        }
+
+        let seq = std::slice::from_raw_parts(seq, seq_len);
+        let qual = (!qual.is_null()).then(|| std::slice::from_raw_parts(qual, seq_len));
+        builder.add_record(seq, qual);
+        Ok(())
+    })
+}
+
+/// Adds the records of a Fastx, SAM/BAM, GFA or 2bit file that are at least
+/// `min_size` long.
+///
+/// # Safety
+///
+/// `builder` must come from `fastoche_builder_new` and `path` must be a
+/// NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn fastoche_builder_add_file(
+    builder: *mut MetricsBuilder,
+    path: *const c_char,
+    min_size: usize,
+) -> c_int {
+    status(|| {
+        let builder = builder.as_mut().ok_or(null_pointer("builder"))?;
+        let path = path_from_c(path)?;
+
+        let config = Config {
+            min_size,
+            genome_size: builder.genome_size,
+            qual_offset: builder.qual_offset,
+            ..Default::default()
+        };
+        builder.merge(build_stats(&[path], &config, &mut None, None));
+        Ok(())
+    })
+}
+
+/// Computes the metrics of the records added so far. The builder can still
+/// be used afterwards.
+///
+/// # Safety
+///
+/// `builder` must come from `fastoche_builder_new` and `metrics` must point
+/// to a `fastoche_metrics`.
+#[no_mangle]
+pub unsafe extern "C" fn fastoche_builder_compute(
+    builder: *const MetricsBuilder,
+    metrics: *mut FastocheMetrics,
+) -> c_int {
+    status(|| {
+        let builder = builder.as_ref().ok_or(null_pointer("builder"))?;
+        let metrics = metrics.as_mut().ok_or(null_pointer("metrics"))?;
+        if builder.seq_sizes.is_empty() {
+            return Err(invalid_argument("No records were added"));
+        }
+
+        *metrics = FastocheMetrics::from(&builder.clone().compute());
+        Ok(())
+    })
+}
+
+/// Computes the metrics of a file in one call
+///
+/// # Safety
+///
+/// `path` must be a NUL-terminated string and `metrics` must point to a
+/// `fastoche_metrics`.
+#[no_mangle]
+pub unsafe extern "C" fn fastoche_stats_file(
+    path: *const c_char,
+    min_size: usize,
+    genome_size: i64,
+    metrics: *mut FastocheMetrics,
+) -> c_int {
+    let builder = fastoche_builder_new(genome_size, 33);
+    let mut code = fastoche_builder_add_file(builder, path, min_size);
+    if code == FASTOCHE_OK {
+        code = fastoche_builder_compute(builder, metrics);
+    }
+    fastoche_builder_free(builder);
+    code
+}
+
+fn null_pointer(name: &str) -> (c_int, String) {
+    (FASTOCHE_NULL_POINTER, format!("{name} is NULL"))
+}
+
+fn invalid_argument(message: &str) -> (c_int, String) {
+    (FASTOCHE_INVALID_ARGUMENT, message.to_string())
+}
+
+unsafe fn path_from_c(path: *const c_char) -> Result<PathBuf, (c_int, String)> {
+    if path.is_null() {
+        return Err(null_pointer("path"));
+    }
+    CStr::from_ptr(path)
+        .to_str()
+        .map(PathBuf::from)
+        .map_err(|_| invalid_argument("path is not valid UTF-8"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builder() {
+        let builder = fastoche_builder_new(0, 33);
+        let mut metrics = FastocheMetrics::default();
+        unsafe {
+            assert_eq!(
+                fastoche_builder_compute(builder, &mut metrics),
+                FASTOCHE_INVALID_ARGUMENT
+            );
+
+            let seq = b"ACGTNNGGCC";
+            let qual = b"IIIIIIIIII";
+            assert_eq!(
+                fastoche_builder_add_record(builder, seq.as_ptr(), seq.len(), qual.as_ptr()),
+                FASTOCHE_OK
+            );
+            assert_eq!(
+                fastoche_builder_add_record(builder, seq.as_ptr(), 4, std::ptr::null()),
+                FASTOCHE_OK
+            );
+            assert_eq!(fastoche_builder_compute(builder, &mut metrics), FASTOCHE_OK);
+            fastoche_builder_free(builder);
+        }
+
+        assert_eq!(metrics.number, 2);
+        assert_eq!(metrics.cumul, 14);
+        assert_eq!(metrics.number_n, 2);
+        assert_eq!(metrics.number_gc, 8);
+        assert_eq!(metrics.mean_quality, 40);
+    }
+
+    #[test]
+    fn test_stats_file() {
+        let mut metrics = FastocheMetrics::default();
+        let path = CString::new("test_inputs/reads.fastq.gz").unwrap();
+        let code = unsafe { fastoche_stats_file(path.as_ptr(), 0, 0, &mut metrics) };
+        assert_eq!(code, FASTOCHE_OK);
+        assert_eq!(metrics.number, 1000);
+        assert_eq!(metrics.cumul, 5957360);
+
+        let path = CString::new("test_inputs/missing.fastq").unwrap();
+        let code = unsafe { fastoche_stats_file(path.as_ptr(), 0, 0, &mut metrics) };
+        assert_eq!(code, FASTOCHE_ERROR);
+        let message = unsafe { CStr::from_ptr(fastoche_last_error()) };
+        assert!(message.to_str().unwrap().starts_with("File not found"));
+    }
+
+    #[test]
+    fn test_abi_version() {
+        let header = std::fs::read_to_string("include/fastoche.h").unwrap();
+        assert!(header.contains(&format!(
+            "#define FASTOCHE_ABI_VERSION {}",
+            fastoche_abi_version()
+        )));
+    }
+}
@@ -0,0 +1,150 @@
+use crate::metrics::compute_avg_quality;
+use crate::parser::Config;
+use crate::reader::Record;
+
+/// Filters a record can fail, in the order they are checked
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Filter {
+    MinSize,
+    MaxSize,
+    MinQual,
+    MaxNFrac,
+    MinGc,
+    MaxGc,
+}
+
+impl Filter {
+    pub const ALL: [Filter; 6] = [
+        Filter::MinSize,
+        Filter::MaxSize,
+        Filter::MinQual,
+        Filter::MaxNFrac,
+        Filter::MinGc,
+        Filter::MaxGc,
+    ];
+
+    /// Command line option of the filter
+    pub fn flag(self) -> &'static str {
+        match self {
+            Filter::MinSize => "--min-size",
+            Filter::MaxSize => "--max-size",
+            Filter::MinQual => "--min-qual",
+            Filter::MaxNFrac => "--max-n-frac",
+            Filter::MinGc => "--min-gc",
+            Filter::MaxGc => "--max-gc",
+        }
+    }
+
+    /// Suffix of the removed reads and bases fields of the CSV output
+    pub fn field(self) -> &'static str {
+        match self {
+            Filter::MinSize => "min_size",
+            Filter::MaxSize => "max_size",
+            Filter::MinQual => "min_qual",
+            Filter::MaxNFrac => "max_n_frac",
+            Filter::MinGc => "min_gc",
+            Filter::MaxGc => "max_gc",
+        }
+    }
+}
+
+/// First filter of the config that a record fails, if any. Records without
+/// qualities pass the quality filter, and records without sequence (GFA
+/// segments only holding a length) pass the N and GC filters.
+pub fn failed_filter(record: &Record, config: &Config) -> Option<Filter> {
+    let record_len = record.len();
+    if record_len < config.min_size {
+        return Some(Filter::MinSize);
+    }
+    if config.max_size.is_some_and(|max| record_len > max) {
+        return Some(Filter::MaxSize);
+    }
+
+    if let (Some(min_qual), Some(qual)) = (config.min_qual, record.qual()) {
+        if compute_avg_quality(qual, config.qual_offset) < min_qual {
+            return Some(Filter::MinQual);
+        }
+    }
+
+    if config.max_n_frac.is_none() && config.min_gc.is_none() && config.max_gc.is_none() {
+        return None;
+    }
+    let seq = record.seq();
+    if seq.len() != record_len {
+        return None;
+    }
+    let count = |bases: &[u8]| {
+        seq.iter()
+            .filter(|c| bases.contains(&c.to_ascii_uppercase()))
+            .count() as f64
+    };
+
+    let len = record_len as f64;
+    if config.max_n_frac.is_some_and(|max| count(b"N") > max * len) {
+        return Some(Filter::MaxNFrac);
+    }
+    // GC percentages, as in the report
+    let percent_gc = count(b"GC") * 100.0 / len;
+    if config.min_gc.is_some_and(|min| percent_gc < min) {
+        return Some(Filter::MinGc);
+    }
+    if config.max_gc.is_some_and(|max| percent_gc > max) {
+        return Some(Filter::MaxGc);
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::reader::get_reader;
+    use std::path::Path;
+
+    #[test]
+    fn test_failed_filter() {
+        // chr1 holds 36 bases, 10 N and 16 GC, chr2 21 bases, 4 N and 8 GC
+        let config = Config {
+            max_n_frac: Some(0.2),
+            min_gc: Some(35.0),
+            ..Default::default()
+        };
+        let mut reader = get_reader(Path::new("test_inputs/genome.2bit"));
+        assert_eq!(
+            failed_filter(&reader.next_record().unwrap(), &config),
+            Some(Filter::MaxNFrac)
+        );
+        assert_eq!(failed_filter(&reader.next_record().unwrap(), &config), None);
+
+        let config = Config {
+            min_size: 30,
+            max_gc: Some(35.0),
+            ..Default::default()
+        };
+        let mut reader = get_reader(Path::new("test_inputs/genome.2bit"));
+        assert_eq!(
+            failed_filter(&reader.next_record().unwrap(), &config),
+            Some(Filter::MaxGc)
+        );
+        assert_eq!(
+            failed_filter(&reader.next_record().unwrap(), &config),
+            Some(Filter::MinSize)
+        );
+    }
+
+    #[test]
+    fn test_segment_without_sequence() {
+        let config = Config {
+            min_gc: Some(10.0),
+            ..Default::default()
+        };
+        let mut reader = get_reader(Path::new("test_inputs/graph.gfa"));
+        let mut failed = Vec::new();
+        while let Some(record) = reader.next_record() {
+            failed.push(failed_filter(&record, &config));
+        }
+        assert_eq!(failed[0], Some(Filter::MinGc));
+        // Segment c only has a length, which is not 0% GC
+        assert_eq!(failed[2], None);
+    }
+}
@@ -32,6 +32,8 @@ pub struct FormattedMetrics {
     pub number_n: String,
     #[tabled(rename = "GC Number")]
     pub number_gc: String,
+    #[tabled(rename = "Soft-masked")]
+    pub number_masked: String,
 
     #[tabled(rename = "NG50 (LG50)")]
     pub ng50_lg50: String,
@@ -42,6 +44,50 @@ pub struct FormattedMetrics {
 
     #[tabled(rename = "Mean quality")]
     pub mean_quality: String,
+
+    #[tabled(rename = "Checksum")]
+    pub checksum: String,
+
+    #[tabled(rename = "Pairs")]
+    pub pairs: String,
+    #[tabled(rename = "Short mate pairs")]
+    pub short_pairs: String,
+
+    #[tabled(rename = "Skipped secondary")]
+    pub secondary: String,
+    #[tabled(rename = "Skipped supplementary")]
+    pub supplementary: String,
+
+    #[tabled(rename = "rq < Q10")]
+    pub rq_below_q10: String,
+    #[tabled(rename = "rq Q10-Q19")]
+    pub rq_q10_q19: String,
+    #[tabled(rename = "rq Q20-Q29")]
+    pub rq_q20_q29: String,
+    #[tabled(rename = "rq Q30-Q39")]
+    pub rq_q30_q39: String,
+    #[tabled(rename = "rq >= Q40")]
+    pub rq_q40_above: String,
+    #[tabled(rename = "HiFi reads (>= Q20)")]
+    pub hifi_reads: String,
+    #[tabled(rename = "HiFi yield")]
+    pub hifi_yield: String,
+    #[tabled(rename = "Mean passes")]
+    pub mean_passes: String,
+
+    #[tabled(rename = "Links")]
+    pub links: String,
+    #[tabled(rename = "Dead ends")]
+    pub dead_ends: String,
+    #[tabled(rename = "Connected components")]
+    pub components: String,
+
+    #[tabled(rename = "Starts with M")]
+    pub starts_with_m: String,
+    #[tabled(rename = "Ends with *")]
+    pub ends_with_stop: String,
+    #[tabled(rename = "Internal stops")]
+    pub internal_stops: String,
 }
 
 impl FormattedMetrics {
@@ -83,16 +129,61 @@ impl FormattedMetrics {
         ng90_lg90.push(')');
 
         let mut number_n = String::new();
-        number_n.push_str(&metrics.number_n.separate_with_commas());
-        number_n.push_str(" (");
-        number_n.push_str(&format!("{:.2}", metrics.percent_n).separate_with_commas());
-        number_n.push_str("%)");
-
         let mut number_gc = String::new();
-        number_gc.push_str(&metrics.number_gc.separate_with_commas());
-        number_gc.push_str(" (");
-        number_gc.push_str(&format!("{:.2}", metrics.percent_gc).separate_with_commas());
-        number_gc.push_str("%)");
+        let mut number_masked = String::new();
+        if metrics.has_composition && !metrics.protein {
+            number_n.push_str(&metrics.number_n.separate_with_commas());
+            number_n.push_str(" (");
+            number_n.push_str(&format!("{:.2}", metrics.percent_n).separate_with_commas());
+            number_n.push_str("%)");
+
+            number_gc.push_str(&metrics.number_gc.separate_with_commas());
+            number_gc.push_str(" (");
+            number_gc.push_str(&format!("{:.2}", metrics.percent_gc).separate_with_commas());
+            number_gc.push_str("%)");
+
+            number_masked.push_str(&metrics.number_masked.separate_with_commas());
+            number_masked.push_str(" (");
+            number_masked
+                .push_str(&format!("{:.2}", metrics.percent_masked).separate_with_commas());
+            number_masked.push_str("%)");
+        } else {
+            number_n.push_str("NA");
+            number_gc.push_str("NA");
+            number_masked.push_str("NA");
+        }
+
+        let mut short_pairs = String::new();
+        short_pairs.push_str(&metrics.short_pairs.separate_with_commas());
+        short_pairs.push_str(" (");
+        short_pairs.push_str(&format!("{:.2}", metrics.percent_short_pairs).separate_with_commas());
+        short_pairs.push_str("%)");
+
+        let mut hifi_reads = String::new();
+        hifi_reads.push_str(&metrics.hifi_reads.separate_with_commas());
+        hifi_reads.push_str(" (");
+        hifi_reads.push_str(&format!("{:.2}", metrics.percent_hifi_reads).separate_with_commas());
+        hifi_reads.push_str("%)");
+
+        let mut hifi_yield = String::new();
+        hifi_yield.push_str(&metrics.hifi_yield.separate_with_commas());
+        hifi_yield.push_str(" (");
+        hifi_yield.push_str(&format!("{:.2}", metrics.percent_hifi_yield).separate_with_commas());
+        hifi_yield.push_str("%)");
+
+        let mut starts_with_m = String::new();
+        starts_with_m.push_str(&metrics.starts_with_m.separate_with_commas());
+        starts_with_m.push_str(" (");
+        starts_with_m
+            .push_str(&format!("{:.2}", metrics.percent_starts_with_m).separate_with_commas());
+        starts_with_m.push_str("%)");
+
+        let mut ends_with_stop = String::new();
+        ends_with_stop.push_str(&metrics.ends_with_stop.separate_with_commas());
+        ends_with_stop.push_str(" (");
+        ends_with_stop
+            .push_str(&format!("{:.2}", metrics.percent_ends_with_stop).separate_with_commas());
+        ends_with_stop.push_str("%)");
 
         Self {
             basename: metrics.filename.clone(),
@@ -104,6 +195,7 @@ impl FormattedMetrics {
             aun: metrics.aun.separate_with_commas(),
             number_n,
             number_gc,
+            number_masked,
             n50_l50,
             n80_l80,
             n90_l90,
@@ -111,6 +203,25 @@ impl FormattedMetrics {
             ng80_lg80,
             ng90_lg90,
             mean_quality: metrics.mean_quality.to_string(),
+            checksum: metrics.checksum_digest.clone(),
+            pairs: metrics.pairs.separate_with_commas(),
+            short_pairs,
+            secondary: metrics.secondary.separate_with_commas(),
+            supplementary: metrics.supplementary.separate_with_commas(),
+            rq_below_q10: metrics.rq_bins[0].separate_with_commas(),
+            rq_q10_q19: metrics.rq_bins[1].separate_with_commas(),
+            rq_q20_q29: metrics.rq_bins[2].separate_with_commas(),
+            rq_q30_q39: metrics.rq_bins[3].separate_with_commas(),
+            rq_q40_above: metrics.rq_bins[4].separate_with_commas(),
+            hifi_reads,
+            hifi_yield,
+            mean_passes: format!("{:.2}", metrics.mean_passes),
+            links: metrics.links.separate_with_commas(),
+            dead_ends: metrics.dead_ends.separate_with_commas(),
+            components: metrics.components.separate_with_commas(),
+            starts_with_m,
+            ends_with_stop,
+            internal_stops: metrics.internal_stops.separate_with_commas(),
         }
     }
 }
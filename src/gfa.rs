@@ -0,0 +1,173 @@
+use crate::metrics::MetricsBuilder;
+use std::collections::HashMap;
+use std::io::BufRead;
+
+/// Segment of a GFA 1 graph. The sequence is empty when the file only gives
+/// its length with a LN tag.
+#[derive(Debug, Default)]
+pub struct Segment {
+    pub name: Vec<u8>,
+    pub seq: Vec<u8>,
+    pub len: usize,
+    /// Bytes of the segment line, with the other lines before it
+    pub input_bytes: u64,
+}
+
+/// Graph-level metrics of a GFA file
+#[derive(Debug, Default, Clone, Copy)]
+pub struct GraphStats {
+    pub links: usize,
+    pub dead_ends: usize,
+    pub components: usize,
+}
+
+/// Reads the segments of a GFA 1 file, keeping the links aside to compute
+/// the graph metrics once every segment is known.
+pub struct GfaReader {
+    reader: Box<dyn BufRead>,
+    line: Vec<u8>,
+    segment: Segment,
+    segment_names: Vec<Vec<u8>>,
+    links: Vec<(Vec<u8>, bool, Vec<u8>, bool)>,
+}
+
+impl GfaReader {
+    pub fn new(reader: Box<dyn BufRead>) -> Self {
+        GfaReader {
+            reader,
+            line: Vec::new(),
+            segment: Segment::default(),
+            segment_names: Vec::new(),
+            links: Vec::new(),
+        }
+    }
+
+    // Not an Iterator, as the segment is reused for each line
+    pub fn next_record(&mut self) -> Option<&Segment> {
+        let mut bytes = 0;
+        loop {
+            self.line.clear();
+            let read = self
+                .reader
+                .read_until(b'\n', &mut self.line)
+                .unwrap_or_else(|e| panic!("Failed to read GFA file: {e}"));
+            if read == 0 {
+                return None;
+            }
+            bytes += read as u64;
+
+            let line = self.line.strip_suffix(b"\n").unwrap_or(&self.line);
+            let line = line.strip_suffix(b"\r").unwrap_or(line);
+            let mut columns = line.split(|c| *c == b'\t');
+
+            match columns.next() {
+                Some(b"S") => {
+                    let name = columns.next().expect("GFA segment without name");
+                    let seq = columns.next().expect("GFA segment without sequence");
+
+                    self.segment.name.clear();
+                    self.segment.name.extend_from_slice(name);
+                    self.segment.seq.clear();
+                    if seq == b"*" {
+                        self.segment.len = columns
+                            .find_map(|tag| tag.strip_prefix(b"LN:i:"))
+                            .and_then(|len| std::str::from_utf8(len).ok()?.parse().ok())
+                            .unwrap_or_else(|| {
+                                panic!(
+                                    "GFA segment {} has neither a sequence nor a LN tag",
+                                    String::from_utf8_lossy(name)
+                                )
+                            });
+                    } else {
+                        self.segment.seq.extend_from_slice(seq);
+                        self.segment.len = seq.len();
+                    }
+
+                    self.segment.input_bytes = bytes;
+                    self.segment_names.push(self.segment.name.clone());
+                    return Some(&self.segment);
+                }
+                Some(b"L") => {
+                    let fields = columns.take(4).collect::<Vec<&[u8]>>();
+                    assert!(
+                        fields.len() == 4,
+                        "Invalid GFA link: {}",
+                        String::from_utf8_lossy(line)
+                    );
+                    self.links.push((
+                        fields[0].to_vec(),
+                        fields[1] == b"+",
+                        fields[2].to_vec(),
+                        fields[3] == b"+",
+                    ));
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Graph metrics of the segments and links read so far, which fails when
+    /// a link names a segment that was not read
+    pub fn graph_stats(&self) -> Result<GraphStats, String> {
+        let ids = self
+            .segment_names
+            .iter()
+            .enumerate()
+            .map(|(i, name)| (name.as_slice(), i))
+            .collect::<HashMap<&[u8], usize>>();
+
+        // Each segment has a start (2 * i) and an end (2 * i + 1)
+        let mut connected_ends = vec![false; 2 * ids.len()];
+        let mut parents = (0..ids.len()).collect::<Vec<usize>>();
+
+        for (from, from_forward, to, to_forward) in &self.links {
+            let (Some(from), Some(to)) = (ids.get(from.as_slice()), ids.get(to.as_slice())) else {
+                return Err(format!(
+                    "GFA link between unknown segments {} and {}",
+                    String::from_utf8_lossy(from),
+                    String::from_utf8_lossy(to)
+                ));
+            };
+
+            // A link leaves the end of a forward segment and enters the start of a forward one
+            connected_ends[2 * from + *from_forward as usize] = true;
+            connected_ends[2 * to + !*to_forward as usize] = true;
+
+            let (from_root, to_root) = (find(&mut parents, *from), find(&mut parents, *to));
+            parents[from_root] = to_root;
+        }
+
+        let components = (0..parents.len())
+            .filter(|i| find(&mut parents, *i) == *i)
+            .count();
+
+        Ok(GraphStats {
+            links: self.links.len(),
+            dead_ends: connected_ends.iter().filter(|c| !**c).count(),
+            components,
+        })
+    }
+}
+
+impl GraphStats {
+    /// Adds the stats of another graph, disconnected from this one
+    pub fn merge(&mut self, other: &GraphStats) {
+        self.links += other.links;
+        self.dead_ends += other.dead_ends;
+        self.components += other.components;
+    }
+
+    pub fn add_to(&self, builder: &mut MetricsBuilder) {
+        builder.links += self.links;
+        builder.dead_ends += self.dead_ends;
+        builder.components += self.components;
+    }
+}
+
+fn find(parents: &mut [usize], mut i: usize) -> usize {
+    while parents[i] != i {
+        parents[i] = parents[parents[i]];
+        i = parents[i];
+    }
+    i
+}
@@ -0,0 +1,47 @@
+/// Equal-width histogram of sequence lengths, as numpy returns it: `bins + 1`
+/// edges and `bins` counts. The last bin includes the maximum length.
+pub fn length_histogram(sizes: &[usize], bins: usize) -> (Vec<f64>, Vec<usize>) {
+    let (Some(min), Some(max)) = (sizes.iter().min(), sizes.iter().max()) else {
+        return (Vec::new(), Vec::new());
+    };
+    if bins == 0 {
+        return (Vec::new(), Vec::new());
+    }
+
+    // A single length gets a bin of width 1 centered on it
+    let (start, end) = if min == max {
+        (*min as f64 - 0.5, *max as f64 + 0.5)
+    } else {
+        (*min as f64, *max as f64)
+    };
+    let width = (end - start) / bins as f64;
+
+    let edges = (0..=bins)
+        .map(|i| start + i as f64 * width)
+        .collect::<Vec<f64>>();
+    let mut counts = vec![0; bins];
+    for size in sizes {
+        let bin = ((*size as f64 - start) / width) as usize;
+        counts[bin.min(bins - 1)] += 1;
+    }
+
+    (edges, counts)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_length_histogram() {
+        let (edges, counts) = length_histogram(&[100, 150, 199, 200, 300], 4);
+        assert_eq!(edges, vec![100.0, 150.0, 200.0, 250.0, 300.0]);
+        assert_eq!(counts, vec![1, 2, 1, 1]);
+
+        let (edges, counts) = length_histogram(&[50, 50], 1);
+        assert_eq!(edges, vec![49.5, 50.5]);
+        assert_eq!(counts, vec![2]);
+
+        assert_eq!(length_histogram(&[], 10), (Vec::new(), Vec::new()));
+    }
+}
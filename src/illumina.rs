@@ -0,0 +1,93 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Fields of Casava 1.8+ read headers:
+/// `@instrument:run:flowcell:lane:tile:x:y read:filtered:control:index`
+#[derive(Debug, Default)]
+pub struct IlluminaHeader<'a> {
+    pub lane: &'a str,
+    pub tile: &'a str,
+    pub index: Option<&'a str>,
+}
+
+pub fn parse_header(id: &[u8]) -> Option<IlluminaHeader<'_>> {
+    let id = std::str::from_utf8(id).ok()?;
+    let mut tokens = id.split_ascii_whitespace();
+
+    let name = tokens.next()?.split(':').collect::<Vec<&str>>();
+    if name.len() != 7 {
+        return None;
+    }
+
+    // The index is missing from some headers, and replaced by the sample number in others
+    let index = tokens
+        .next()
+        .and_then(|comment| comment.split(':').nth(3))
+        .filter(|index| !index.is_empty() && !index.bytes().all(|c| c.is_ascii_digit()));
+
+    Some(IlluminaHeader {
+        lane: name[3],
+        tile: name[4],
+        index,
+    })
+}
+
+/// Read counts of the index sequences found in the headers
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct IlluminaStats {
+    pub index_counts: HashMap<String, usize>,
+    pub reads: usize,
+}
+
+impl IlluminaStats {
+    pub fn add_record(&mut self, id: &[u8]) {
+        self.reads += 1;
+
+        if let Some(index) = parse_header(id).and_then(|header| header.index) {
+            match self.index_counts.get_mut(index) {
+                Some(count) => *count += 1,
+                None => {
+                    self.index_counts.insert(index.to_string(), 1);
+                }
+            }
+        }
+    }
+
+    pub fn merge(&mut self, other: IlluminaStats) {
+        self.reads += other.reads;
+        for (index, count) in other.index_counts {
+            *self.index_counts.entry(index).or_default() += count;
+        }
+    }
+
+    /// Most frequent index sequences, in decreasing order of read count
+    pub fn top_indexes(&self, n: usize) -> Vec<(&str, usize)> {
+        let mut indexes = self
+            .index_counts
+            .iter()
+            .map(|(index, count)| (index.as_str(), *count))
+            .collect::<Vec<(&str, usize)>>();
+        indexes.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+        indexes.truncate(n);
+        indexes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_header() {
+        let header =
+            parse_header(b"A00123:8:H7KJNDSXX:2:1101:1000:2000 1:N:0:ACGTACGT+TTGGCCAA").unwrap();
+        assert_eq!(header.lane, "2");
+        assert_eq!(header.tile, "1101");
+        assert_eq!(header.index, Some("ACGTACGT+TTGGCCAA"));
+
+        let header = parse_header(b"A00123:8:H7KJNDSXX:2:1101:1000:2000 1:N:0:3").unwrap();
+        assert_eq!(header.index, None);
+
+        assert!(parse_header(b"read_1/1").is_none());
+    }
+}
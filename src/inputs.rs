@@ -0,0 +1,334 @@
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+/// Extensions of the files picked up when expanding directories and globs
+const INPUT_EXTENSIONS: [&str; 11] = [
+    "fa", "fasta", "fna", "fq", "fastq", "fas", "bam", "sam", "gfa", "2bit", "faa",
+];
+
+/// One column of the report: a single file, or several files named with the
+/// `name=file_1,file_2` syntax whose records are pooled together.
+#[derive(Debug, Clone)]
+pub struct Input {
+    pub name: Option<String>,
+    pub paths: Vec<PathBuf>,
+}
+
+/// Parses a `-f` value: a path, or `name=file_1,file_2`. Values that exist
+/// as a path, or whose part before the first '=' holds a '/', are paths, so
+/// that files and directories with a '=' in their name can be given as is.
+pub fn parse_input(value: &str) -> Result<Input, String> {
+    let named = value
+        .split_once('=')
+        .filter(|(name, _)| !name.contains('/') && !Path::new(value).exists());
+    match named {
+        Some((name, paths)) => {
+            if name.is_empty() {
+                return Err(format!("Missing sample name in {value}"));
+            }
+            let paths = paths
+                .split(',')
+                .filter(|p| !p.is_empty())
+                .map(PathBuf::from)
+                .collect::<Vec<PathBuf>>();
+            if paths.is_empty() {
+                return Err(format!("No file given for sample {name}"));
+            }
+
+            Ok(Input {
+                name: Some(name.to_string()),
+                paths,
+            })
+        }
+        None => Ok(Input {
+            name: None,
+            paths: vec![PathBuf::from(value)],
+        }),
+    }
+}
+
+/// Reads a file of filenames: one path per line, optionally followed by a
+/// tab and the name to display. Paths may hold spaces. Empty lines and lines
+/// starting with '#' are ignored. The names must be unique.
+pub fn read_fofn(fofn: &PathBuf) -> Result<Vec<Input>, String> {
+    let content = std::fs::read_to_string(fofn)
+        .map_err(|e| format!("Failed to read file of filenames {fofn:?}: {e}"))?;
+
+    let inputs = content
+        .lines()
+        .map(|line| line.trim())
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| match line.split_once('\t') {
+            Some((path, name)) => Input {
+                name: Some(name.trim().to_string()),
+                paths: vec![PathBuf::from(path.trim_end())],
+            },
+            None => Input {
+                name: None,
+                paths: vec![PathBuf::from(line)],
+            },
+        })
+        .collect::<Vec<Input>>();
+    check_names(inputs.iter().filter_map(|input| input.name.as_deref()))?;
+
+    Ok(inputs)
+}
+
+/// Replaces directories and glob patterns by the supported files they hold.
+/// An unnamed directory or pattern gives one input per file, while the files
+/// of a named group stay pooled together.
+pub fn expand_inputs(inputs: Vec<Input>) -> Vec<Input> {
+    let mut expanded = Vec::new();
+
+    for input in inputs {
+        let paths = input
+            .paths
+            .iter()
+            .flat_map(expand_path)
+            .collect::<Vec<PathBuf>>();
+
+        if input.name.is_some() || input.paths.len() > 1 {
+            expanded.push(Input {
+                name: input.name,
+                paths,
+            });
+        } else {
+            expanded.extend(paths.into_iter().map(|path| Input {
+                name: None,
+                paths: vec![path],
+            }));
+        }
+    }
+
+    expanded
+}
+
+/// Names the inputs with a comma-separated list holding exactly one unique
+/// name per input.
+pub fn rename_inputs(inputs: &mut [Input], rename: &str) -> Result<(), String> {
+    let names = rename.split(',').collect::<Vec<&str>>();
+    if names.len() != inputs.len() {
+        return Err(format!(
+            "{} names were given to --rename for {} inputs",
+            names.len(),
+            inputs.len()
+        ));
+    }
+    check_names(names.iter().copied())?;
+
+    for (input, name) in inputs.iter_mut().zip(names) {
+        input.name = Some(name.to_string());
+    }
+
+    Ok(())
+}
+
+/// Names the inputs from a file where each line holds a path, a tab and the
+/// name to display. Inputs whose path is not listed keep their name. Paths are
+/// compared once canonicalized, so `./reads.fq` matches `reads.fq`.
+pub fn rename_inputs_from_file(inputs: &mut [Input], rename_file: &PathBuf) -> Result<(), String> {
+    let content = std::fs::read_to_string(rename_file)
+        .map_err(|e| format!("Failed to read {rename_file:?}: {e}"))?;
+
+    let mut mapping = HashMap::new();
+    for (i, line) in content.lines().enumerate() {
+        if line.trim().is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let (path, name) = line
+            .split_once('\t')
+            .ok_or_else(|| format!("Line {} of {rename_file:?} is not 'path<TAB>name'", i + 1))?;
+        if mapping
+            .insert(canonical_path(Path::new(path)), (path, name.trim()))
+            .is_some()
+        {
+            return Err(format!("{path} is listed several times in {rename_file:?}"));
+        }
+    }
+    check_names(mapping.values().map(|(_, name)| *name))?;
+
+    let mut used = HashSet::new();
+    for input in inputs.iter_mut() {
+        let found = input.paths.iter().find_map(|p| {
+            let path = canonical_path(p);
+            mapping.get(&path).map(|(_, name)| (path, *name))
+        });
+        if let Some((path, name)) = found {
+            input.name = Some(name.to_string());
+            used.insert(path);
+        }
+    }
+
+    for (path, (line_path, _)) in &mapping {
+        if !used.contains(path) {
+            eprintln!("Warning: {line_path} of {rename_file:?} matches no input");
+        }
+    }
+
+    // Renamed inputs may now share the name of another input
+    check_names(inputs.iter().filter_map(|input| input.name.as_deref()))
+}
+
+fn canonical_path(path: &Path) -> PathBuf {
+    path.canonicalize().unwrap_or_else(|_| path.to_path_buf())
+}
+
+fn check_names<'a>(names: impl Iterator<Item = &'a str>) -> Result<(), String> {
+    let mut seen = HashSet::new();
+    for name in names {
+        if name.is_empty() {
+            return Err("Names can not be empty".to_string());
+        }
+        if !seen.insert(name) {
+            return Err(format!("Name {name} is given several times"));
+        }
+    }
+
+    Ok(())
+}
+
+fn expand_path(path: &PathBuf) -> Vec<PathBuf> {
+    let pattern = path.to_str().unwrap();
+
+    let mut paths = if path.is_dir() {
+        std::fs::read_dir(path)
+            .unwrap_or_else(|e| panic!("Failed to read directory {path:?}: {e}"))
+            .map(|entry| entry.expect("Failed to read directory entry").path())
+            .filter(|p| p.is_file() && is_supported(p))
+            .collect::<Vec<PathBuf>>()
+    } else if !path.exists() && pattern.contains(['*', '?', '[']) {
+        glob::glob(pattern)
+            .unwrap_or_else(|e| panic!("Invalid glob pattern {pattern}: {e}"))
+            .map(|entry| entry.expect("Failed to read glob entry"))
+            .filter(|p| p.is_file() && is_supported(p))
+            .collect::<Vec<PathBuf>>()
+    } else {
+        return vec![path.clone()];
+    };
+
+    assert!(!paths.is_empty(), "No supported file found in {path:?}");
+    paths.sort();
+    paths
+}
+
+fn is_supported(path: &std::path::Path) -> bool {
+    let (path, gzipped) = match path.extension() {
+        Some(ext) if ext == "gz" => (path.with_extension(""), true),
+        _ => (path.to_path_buf(), false),
+    };
+
+    // BAM files are already compressed, and 2bit files need random access
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| {
+            INPUT_EXTENSIONS.contains(&ext) && !(gzipped && (ext == "bam" || ext == "2bit"))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_input() {
+        let input = parse_input("sample=a.fq,b.fq").unwrap();
+        assert_eq!(input.name.as_deref(), Some("sample"));
+        assert_eq!(input.paths, [PathBuf::from("a.fq"), PathBuf::from("b.fq")]);
+
+        let input = parse_input("runs/date=2024/reads.fq").unwrap();
+        assert_eq!(input.name, None);
+        assert_eq!(input.paths, [PathBuf::from("runs/date=2024/reads.fq")]);
+
+        let dir = std::env::temp_dir().join(format!("fastoche_k={}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("reads.fa"), ">a\nACGT\n").unwrap();
+        let inputs = expand_inputs(vec![parse_input(dir.to_str().unwrap()).unwrap()]);
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(inputs.len(), 1);
+        assert_eq!(inputs[0].name, None);
+        assert_eq!(inputs[0].paths, [dir.join("reads.fa")]);
+
+        assert!(parse_input("=a.fq").is_err());
+    }
+
+    #[test]
+    fn test_read_fofn() {
+        let path = std::env::temp_dir().join(format!("fastoche_fofn_{}.txt", std::process::id()));
+        std::fs::write(
+            &path,
+            "# reads\nruns/run 1/reads.fq.gz\tRun 1\n\nassembly.fa\n",
+        )
+        .unwrap();
+        let inputs = read_fofn(&path).unwrap();
+
+        assert_eq!(inputs.len(), 2);
+        assert_eq!(inputs[0].name.as_deref(), Some("Run 1"));
+        assert_eq!(inputs[0].paths, [PathBuf::from("runs/run 1/reads.fq.gz")]);
+        assert_eq!(inputs[1].name, None);
+        assert_eq!(inputs[1].paths, [PathBuf::from("assembly.fa")]);
+
+        std::fs::write(&path, "a.fa\tSample\nb.fa\tSample\n").unwrap();
+        let inputs = read_fofn(&path);
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(inputs.unwrap_err(), "Name Sample is given several times");
+    }
+
+    #[test]
+    fn test_expand_directory() {
+        let inputs = expand_inputs(vec![parse_input("test_inputs").unwrap()]);
+        let paths = inputs
+            .iter()
+            .map(|i| i.paths[0].to_str().unwrap())
+            .collect::<Vec<&str>>();
+        assert!(paths.contains(&"test_inputs/reads.fastq.gz"));
+        assert!(paths.contains(&"test_inputs/reads.bam"));
+    }
+
+    #[test]
+    fn test_expand_glob_in_group() {
+        let inputs = expand_inputs(vec![
+            parse_input("pairs=test_inputs/pairs_R*.fastq.gz").unwrap()
+        ]);
+        assert_eq!(inputs.len(), 1);
+        assert_eq!(
+            inputs[0].paths,
+            vec![
+                PathBuf::from("test_inputs/pairs_R1.fastq.gz"),
+                PathBuf::from("test_inputs/pairs_R2.fastq.gz")
+            ]
+        );
+    }
+
+    #[test]
+    fn test_rename_inputs() {
+        let mut inputs = vec![parse_input("a.fa").unwrap(), parse_input("b.fa").unwrap()];
+        assert!(rename_inputs(&mut inputs, "first").is_err());
+        assert!(rename_inputs(&mut inputs, "first,second,third").is_err());
+        assert!(rename_inputs(&mut inputs, "first,first").is_err());
+
+        rename_inputs(&mut inputs, "first,second").unwrap();
+        assert_eq!(inputs[1].name.as_deref(), Some("second"));
+    }
+
+    #[test]
+    fn test_rename_inputs_from_file() {
+        let path = std::env::temp_dir().join(format!("fastoche_rename_{}.tsv", std::process::id()));
+        std::fs::write(&path, "./test_inputs/reads.sam\tSAM\nmissing.fa\tMissing\n").unwrap();
+
+        let mut inputs = vec![
+            parse_input("test_inputs/reads.sam").unwrap(),
+            parse_input("test_inputs/reads.bam").unwrap(),
+        ];
+        rename_inputs_from_file(&mut inputs, &path).unwrap();
+        assert_eq!(inputs[0].name.as_deref(), Some("SAM"));
+        assert_eq!(inputs[1].name, None);
+
+        let mut inputs = vec![
+            parse_input("SAM=test_inputs/reads.bam").unwrap(),
+            parse_input("test_inputs/reads.sam").unwrap(),
+        ];
+        let renamed = rename_inputs_from_file(&mut inputs, &path);
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(renamed, Err("Name SAM is given several times".to_string()));
+    }
+}
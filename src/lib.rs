@@ -0,0 +1,118 @@
+//! Metrics of Fasta, Fastq, SAM/BAM, GFA and 2bit files.
+//!
+//! A `MetricsBuilder` accumulates the records of an input, and its
+//! `compute()` returns the final `Metrics`. The `report` writers output them
+//! as a table, CSV or parsable text to any `io::Write`. Builders can be saved
+//! and merged with the `state` module.
+//!
+//! ```
+//! use fastoche::MetricsBuilder;
+//!
+//! let mut builder = MetricsBuilder::new("contigs.fa", 0, None);
+//! builder.add_record(b"ACGTACGTNN", None);
+//! builder.add_record(b"GGCC", None);
+//!
+//! let metrics = builder.compute();
+//! assert_eq!(metrics.filename, "contigs");
+//! assert_eq!(metrics.cumul, 14);
+//! assert_eq!(metrics.number_n, 2);
+//!
+//! let mut csv = Vec::new();
+//! fastoche::report::write_csv(&mut csv, &[metrics]).unwrap();
+//! ```
+//!
+//! `parse` computes the metrics of whole inputs, as the command line does:
+//!
+//! ```
+//! use fastoche::{parse, parse_input, ChecksumOptions, Config};
+//!
+//! let inputs = [parse_input("reads=test_inputs/reads.sam").unwrap()];
+//! let config = Config {
+//!     checksum: Some(ChecksumOptions::default()),
+//!     ..Default::default()
+//! };
+//!
+//! let metrics = parse(&inputs, &config);
+//! assert_eq!(metrics[0].filename, "reads");
+//! assert_eq!(metrics[0].number, 100);
+//! ```
+
+use std::cell::Cell;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::sync::Once;
+
+mod alignment;
+mod checksum;
+mod cli;
+mod composition;
+mod fai;
+mod ffi;
+mod filter;
+mod formatted_metrics;
+mod gfa;
+mod histogram;
+mod illumina;
+mod inputs;
+mod nanopore;
+mod paired;
+mod parser;
+mod protein;
+#[cfg(feature = "python")]
+mod python;
+mod reader;
+mod sample;
+mod twobit;
+mod validate;
+mod writer;
+
+pub mod metrics;
+pub mod report;
+pub mod state;
+
+pub use checksum::{Checksum, ChecksumOptions};
+pub use cli::run;
+pub use filter::Filter;
+pub use illumina::IlluminaStats;
+pub use inputs::{parse_input, Input};
+pub use metrics::{Metrics, MetricsBuilder};
+pub use nanopore::{NanoporeStats, NanoporeSummary};
+pub use parser::{parse, Config, GroupBy, NonNucleotide};
+pub use sample::{SampleMode, Sampling};
+
+thread_local! {
+    // Whether the thread runs a call of the C or Python bindings, whose
+    // panics are reported to the caller instead of being printed
+    static QUIET_PANICS: Cell<bool> = const { Cell::new(false) };
+}
+
+static QUIET_HOOK: Once = Once::new();
+
+/// Runs `f` at the boundary of the C and Python bindings, returning the
+/// message of its panic, if any. The panic hook of the host process is
+/// wrapped so that these panics do not print a message and a backtrace on
+/// its stderr, while other threads keep the previous hook.
+pub(crate) fn catch_quietly<T>(f: impl FnOnce() -> T) -> Result<T, String> {
+    QUIET_HOOK.call_once(|| {
+        let hook = std::panic::take_hook();
+        std::panic::set_hook(Box::new(move |info| {
+            if !QUIET_PANICS.with(Cell::get) {
+                hook(info);
+            }
+        }));
+    });
+
+    QUIET_PANICS.with(|quiet| quiet.set(true));
+    let result = catch_unwind(AssertUnwindSafe(f));
+    QUIET_PANICS.with(|quiet| quiet.set(false));
+    result.map_err(panic_message)
+}
+
+/// Message of a caught panic
+pub(crate) fn panic_message(panic: Box<dyn std::any::Any + Send>) -> String {
+    match panic.downcast::<String>() {
+        Ok(message) => *message,
+        Err(panic) => panic
+            .downcast_ref::<&str>()
+            .map_or("Unknown error".to_string(), |message| message.to_string()),
+    }
+}
@@ -1,56 +1,258 @@
+use crate::checksum::Checksum;
+use crate::filter::Filter;
+use crate::illumina::IlluminaStats;
+use crate::nanopore::{NanoporeStats, NanoporeSummary};
+use crate::protein::looks_like_protein;
+use crate::sample::Sampling;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use std::{fmt::Display, ops::Index};
 
-#[derive(Debug)]
-pub struct Metrics {
+// Extensions removed from file names to infer the sample names
+const EXTENSIONS: [&str; 11] = [
+    ".fasta", ".fastq", ".fna", ".fa", ".fq", ".fas", ".bam", ".sam", ".gfa", ".2bit", ".faa",
+];
+
+// Predicted accuracies of Q10, Q20, Q30 and Q40
+const RQ_THRESHOLDS: [f32; 4] = [0.9, 0.99, 0.999, 0.9999];
+// HiFi reads are the reads of at least Q20
+const HIFI_THRESHOLD: f32 = RQ_THRESHOLDS[1];
+
+/// Accumulates the records of an input. `compute()` then turns them into
+/// the final `Metrics`. Builders can be saved and merged, so that the
+/// metrics of a file processed in chunks are computed from the raw counts.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MetricsBuilder {
     pub filename: String,
     pub genome_size: i64,
-    pub cumul: usize,
-    pub number: usize,
-    pub min_size: usize,
-    pub max_size: usize,
-    pub avg_size: usize,
-    pub aun: usize,
-    pub number_n: usize,
-    pub percent_n: f64,
-    pub number_gc: usize,
-    pub percent_gc: f64,
-    pub n50: usize,
-    pub l50: usize,
-    pub n80: usize,
-    pub l80: usize,
-    pub n90: usize,
-    pub l90: usize,
-    pub ng50: usize,
-    pub lg50: usize,
-    pub ng80: usize,
-    pub lg80: usize,
-    pub ng90: usize,
-    pub lg90: usize,
+    pub qual_offset: u8,
     pub seq_sizes: Vec<usize>,
+    #[serde(
+        serialize_with = "serialize_counts",
+        deserialize_with = "deserialize_counts"
+    )]
     pub nucleotide_counts: [usize; 256],
-    pub mean_qualities: Vec<f64>,
-    pub mean_quality: usize,
+    pub quality_sum: f64,
+    pub quality_count: usize,
+    pub checksum: Option<Checksum>,
+    pub pairs: usize,
+    pub short_pairs: usize,
+    pub secondary: usize,
+    pub supplementary: usize,
+    pub rq_reads: usize,
+    pub rq_bins: [usize; 5],
+    pub hifi_reads: usize,
+    pub hifi_yield: usize,
+    pub np_reads: usize,
+    pub np_sum: usize,
+    pub group: String,
+    pub nanopore: Option<NanoporeStats>,
+    pub illumina: Option<IlluminaStats>,
+    pub links: usize,
+    pub dead_ends: usize,
+    pub components: usize,
+    pub has_composition: bool,
+    pub protein: bool,
+    pub starts_with_m: usize,
+    pub ends_with_stop: usize,
+    /// Reads and bases removed by each filter, in the order of `Filter::ALL`
+    #[serde(default)]
+    pub removed: [(usize, usize); Filter::ALL.len()],
+    #[serde(default)]
+    pub sampling: Option<Sampling>,
 }
 
-impl Metrics {
+impl MetricsBuilder {
     pub fn new(filename: &str, genome_size: i64, name: Option<String>) -> Self {
         let basename = match name {
             Some(n) => n,
-            None => filename
-                .split('/')
-                .last()
-                .expect("Could not get last element")
-                .replace(".fasta", "")
-                .replace(".fastq", "")
-                .replace(".fa", "")
-                .replace(".fq", "")
-                .replace(".gz", ""),
+            None => {
+                let basename = filename
+                    .split('/')
+                    .next_back()
+                    .expect("Could not get last element");
+                let basename = basename.strip_suffix(".gz").unwrap_or(basename);
+
+                EXTENSIONS
+                    .iter()
+                    .find_map(|ext| basename.strip_suffix(ext))
+                    .unwrap_or(basename)
+                    .to_string()
+            }
         };
 
-        Metrics {
+        MetricsBuilder {
             filename: basename,
             genome_size,
+            qual_offset: 33,
+            seq_sizes: Vec::new(),
+            nucleotide_counts: [0; 256],
+            quality_sum: 0.0,
+            quality_count: 0,
+            checksum: None,
+            pairs: 0,
+            short_pairs: 0,
+            secondary: 0,
+            supplementary: 0,
+            rq_reads: 0,
+            rq_bins: [0; 5],
+            hifi_reads: 0,
+            hifi_yield: 0,
+            np_reads: 0,
+            np_sum: 0,
+            group: String::new(),
+            nanopore: None,
+            illumina: None,
+            links: 0,
+            dead_ends: 0,
+            components: 0,
+            has_composition: true,
+            protein: false,
+            starts_with_m: 0,
+            ends_with_stop: 0,
+            removed: [(0, 0); Filter::ALL.len()],
+            sampling: None,
+        }
+    }
+
+    /// Accumulates a record and returns its mean quality
+    pub fn add_record(&mut self, seq: &[u8], qual: Option<&[u8]>) -> f64 {
+        self.seq_sizes.push(seq.len());
+        for c in seq.iter() {
+            self.nucleotide_counts[*c as usize] += 1;
+        }
+        if seq.first().is_some_and(|c| c.eq_ignore_ascii_case(&b'M')) {
+            self.starts_with_m += 1;
+        }
+        if seq.last() == Some(&b'*') {
+            self.ends_with_stop += 1;
+        }
+        if let Some(checksum) = self.checksum.as_mut() {
+            checksum.add_record(seq, qual);
+        }
+        self.add_quality(qual)
+    }
+
+    /// Counts a record removed by a filter
+    pub fn add_removed(&mut self, filter: Filter, len: usize) {
+        let i = Filter::ALL.iter().position(|f| *f == filter).unwrap();
+        self.removed[i].0 += 1;
+        self.removed[i].1 += len;
+    }
+
+    /// Accumulates a sequence whose length only is known
+    pub fn add_length(&mut self, len: usize) {
+        self.seq_sizes.push(len);
+    }
+
+    /// Accumulates the PacBio predicted accuracy and number of passes of a record
+    pub fn add_pacbio_tags(&mut self, tags: (Option<f32>, Option<u32>), record_len: usize) {
+        let (rq, np) = tags;
+
+        if let Some(rq) = rq {
+            // Binned by steps of 10 of the Phred score up to Q40. The accuracy
+            // is compared to the thresholds, as the Phred score computed
+            // from the f32 accuracy can fall just below them.
+            let bin = RQ_THRESHOLDS.iter().filter(|t| rq >= **t).count();
+            self.rq_bins[bin] += 1;
+            self.rq_reads += 1;
+
+            if rq >= HIFI_THRESHOLD {
+                self.hifi_reads += 1;
+                self.hifi_yield += record_len;
+            }
+        }
+
+        if let Some(np) = np {
+            self.np_reads += 1;
+            self.np_sum += np as usize;
+        }
+    }
+
+    fn add_quality(&mut self, qualities: Option<&[u8]>) -> f64 {
+        match qualities {
+            Some(qualities) => {
+                let avg_quality = compute_avg_quality(qualities, self.qual_offset);
+                self.quality_sum += avg_quality;
+                self.quality_count += 1;
+                avg_quality
+            }
+            None => 0.0,
+        }
+    }
+
+    /// Adds the records accumulated by another builder of the same input
+    pub fn merge(&mut self, other: MetricsBuilder) {
+        self.seq_sizes.extend(other.seq_sizes);
+        for (count, other_count) in self
+            .nucleotide_counts
+            .iter_mut()
+            .zip(other.nucleotide_counts)
+        {
+            *count += other_count;
+        }
+        self.quality_sum += other.quality_sum;
+        self.quality_count += other.quality_count;
+
+        match (&mut self.checksum, &other.checksum) {
+            (Some(checksum), Some(other_checksum)) => checksum.merge(other_checksum),
+            (None, None) => {}
+            _ => panic!(
+                "Cannot merge {} computed with and without checksum",
+                self.filename
+            ),
+        }
+
+        self.pairs += other.pairs;
+        self.short_pairs += other.short_pairs;
+        self.secondary += other.secondary;
+        self.supplementary += other.supplementary;
+        self.rq_reads += other.rq_reads;
+        for (bin, other_bin) in self.rq_bins.iter_mut().zip(other.rq_bins) {
+            *bin += other_bin;
+        }
+        self.hifi_reads += other.hifi_reads;
+        self.hifi_yield += other.hifi_yield;
+        self.np_reads += other.np_reads;
+        self.np_sum += other.np_sum;
+
+        match (&mut self.nanopore, other.nanopore) {
+            (Some(nanopore), Some(other_nanopore)) => nanopore.merge(other_nanopore),
+            (nanopore @ None, other_nanopore) => *nanopore = other_nanopore,
+            _ => {}
+        }
+        match (&mut self.illumina, other.illumina) {
+            (Some(illumina), Some(other_illumina)) => illumina.merge(other_illumina),
+            (illumina @ None, other_illumina) => *illumina = other_illumina,
+            _ => {}
+        }
+
+        self.links += other.links;
+        self.dead_ends += other.dead_ends;
+        self.components += other.components;
+        self.has_composition &= other.has_composition;
+        self.protein |= other.protein;
+        self.starts_with_m += other.starts_with_m;
+        self.ends_with_stop += other.ends_with_stop;
+        for (removed, other_removed) in self.removed.iter_mut().zip(other.removed) {
+            removed.0 += other_removed.0;
+            removed.1 += other_removed.1;
+        }
+        match (&mut self.sampling, &other.sampling) {
+            (Some(sampling), Some(other_sampling)) => sampling.merge(other_sampling),
+            (sampling @ None, other_sampling) => sampling.clone_from(other_sampling),
+            _ => {}
+        }
+    }
+
+    pub fn compute(self) -> Metrics {
+        let mut seq_sizes = self.seq_sizes;
+        seq_sizes.sort_by(|a, b| b.cmp(a));
+
+        let mut metrics = Metrics {
+            filename: self.filename,
+            genome_size: self.genome_size,
             cumul: 0,
+            sequenced_bases: 0,
             number: 0,
             min_size: 0,
             max_size: 0,
@@ -60,6 +262,8 @@ impl Metrics {
             percent_n: 0.0,
             number_gc: 0,
             percent_gc: 0.0,
+            number_masked: 0,
+            percent_masked: 0.0,
             n50: 0,
             l50: 0,
             n80: 0,
@@ -72,56 +276,180 @@ impl Metrics {
             lg80: 0,
             ng90: 0,
             lg90: 0,
-            seq_sizes: Vec::new(),
-            nucleotide_counts: [0; 256],
-            mean_qualities: Vec::new(),
+            nucleotide_counts: self.nucleotide_counts,
             mean_quality: 0,
-        }
-    }
+            checksum_digest: self.checksum.map(|c| c.digest()).unwrap_or_default(),
+            pairs: self.pairs,
+            short_pairs: self.short_pairs,
+            percent_short_pairs: 0.0,
+            secondary: self.secondary,
+            supplementary: self.supplementary,
+            rq_reads: self.rq_reads,
+            rq_bins: self.rq_bins,
+            hifi_reads: self.hifi_reads,
+            percent_hifi_reads: 0.0,
+            hifi_yield: self.hifi_yield,
+            percent_hifi_yield: 0.0,
+            np_reads: self.np_reads,
+            np_sum: self.np_sum,
+            mean_passes: 0.0,
+            group: self.group,
+            nanopore_summary: self.nanopore.as_ref().map(NanoporeStats::summary),
+            nanopore: self.nanopore,
+            illumina: self.illumina,
+            links: self.links,
+            dead_ends: self.dead_ends,
+            components: self.components,
+            has_composition: self.has_composition,
+            protein: self.protein,
+            starts_with_m: self.starts_with_m,
+            percent_starts_with_m: 0.0,
+            ends_with_stop: self.ends_with_stop,
+            percent_ends_with_stop: 0.0,
+            internal_stops: 0,
+            removed: self.removed,
+            sampling: self.sampling,
+        };
+
+        metrics.compute_seq_number(&seq_sizes);
+        metrics.compute_cumul(&seq_sizes);
+        metrics.compute_min_size(&seq_sizes);
+        metrics.compute_max_size(&seq_sizes);
+        metrics.compute_avg_size(&seq_sizes);
+        metrics.compute_sequenced_bases();
+        metrics.compute_number_n();
+        metrics.compute_number_gc();
+        metrics.compute_number_masked();
+        metrics.compute_aun_and_nx_metrics(&seq_sizes);
 
-    pub fn compute(&mut self) {
-        self.seq_sizes.sort_by(|a, b| b.cmp(a));
+        metrics.compute_mean_quality(self.quality_sum, self.quality_count);
+        metrics.compute_percent_short_pairs();
+        metrics.compute_pacbio_metrics();
+        metrics.compute_protein_metrics();
 
-        self.compute_seq_number();
-        self.compute_cumul();
-        self.compute_min_size();
-        self.compute_max_size();
-        self.compute_avg_size();
-        self.compute_number_n();
-        self.compute_number_gc();
-        self.compute_aun_and_nx_metrics();
+        metrics
+    }
+}
 
-        self.seq_sizes = Vec::new();
+/// Mean quality of a read, from the mean of its error probabilities
+pub fn compute_avg_quality(qualities: &[u8], qual_offset: u8) -> f64 {
+    let mut sum_quality: f32 = 0.0;
+    let mut power_values = [0.0f32; 256];
+    let offset = qual_offset as f32;
 
-        self.compute_mean_quality();
+    for q in qualities.iter().map(|q| *q as usize) {
+        if power_values[q] == 0.0 {
+            power_values[q] = 1.0 / 10.0f32.powf((q as f32 - offset) / 10.0);
+        }
+        sum_quality += power_values[q];
     }
+    -10.0 * (sum_quality as f64 / qualities.len() as f64).log10()
+}
 
-    fn compute_seq_number(&mut self) {
-        self.number = self.seq_sizes.len();
+/// Metrics of an input, computed by `MetricsBuilder::compute()`
+#[derive(Debug)]
+pub struct Metrics {
+    pub filename: String,
+    pub genome_size: i64,
+    pub cumul: usize,
+    /// Bases whose sequence is known, out of `cumul`. The composition
+    /// percentages are computed on them, as GFA segments given by a LN tag
+    /// only count in the length metrics.
+    pub sequenced_bases: usize,
+    pub number: usize,
+    pub min_size: usize,
+    pub max_size: usize,
+    pub avg_size: usize,
+    pub aun: usize,
+    pub number_n: usize,
+    pub percent_n: f64,
+    pub number_gc: usize,
+    pub percent_gc: f64,
+    pub number_masked: usize,
+    pub percent_masked: f64,
+    pub n50: usize,
+    pub l50: usize,
+    pub n80: usize,
+    pub l80: usize,
+    pub n90: usize,
+    pub l90: usize,
+    pub ng50: usize,
+    pub lg50: usize,
+    pub ng80: usize,
+    pub lg80: usize,
+    pub ng90: usize,
+    pub lg90: usize,
+    pub nucleotide_counts: [usize; 256],
+    pub mean_quality: usize,
+    pub checksum_digest: String,
+    pub pairs: usize,
+    pub short_pairs: usize,
+    pub percent_short_pairs: f64,
+    pub secondary: usize,
+    pub supplementary: usize,
+    pub rq_reads: usize,
+    pub rq_bins: [usize; 5],
+    pub hifi_reads: usize,
+    pub percent_hifi_reads: f64,
+    pub hifi_yield: usize,
+    pub percent_hifi_yield: f64,
+    pub np_reads: usize,
+    pub np_sum: usize,
+    pub mean_passes: f64,
+    pub group: String,
+    pub nanopore: Option<NanoporeStats>,
+    pub nanopore_summary: Option<NanoporeSummary>,
+    pub illumina: Option<IlluminaStats>,
+    pub links: usize,
+    pub dead_ends: usize,
+    pub components: usize,
+    pub has_composition: bool,
+    pub protein: bool,
+    pub starts_with_m: usize,
+    pub percent_starts_with_m: f64,
+    pub ends_with_stop: usize,
+    pub percent_ends_with_stop: f64,
+    pub internal_stops: usize,
+    pub removed: [(usize, usize); Filter::ALL.len()],
+    pub sampling: Option<Sampling>,
+}
+
+impl Metrics {
+    fn compute_seq_number(&mut self, seq_sizes: &[usize]) {
+        self.number = seq_sizes.len();
     }
 
-    fn compute_cumul(&mut self) {
-        for size in &self.seq_sizes {
+    fn compute_cumul(&mut self, seq_sizes: &[usize]) {
+        for size in seq_sizes {
             self.cumul += *size;
         }
     }
 
-    fn compute_min_size(&mut self) {
-        self.min_size = *self.seq_sizes.last().expect("Failed to get first element");
+    fn compute_min_size(&mut self, seq_sizes: &[usize]) {
+        // Inputs can be empty, or emptied by the filters
+        self.min_size = seq_sizes.last().copied().unwrap_or(0);
+    }
+
+    fn compute_max_size(&mut self, seq_sizes: &[usize]) {
+        self.max_size = seq_sizes.first().copied().unwrap_or(0);
     }
 
-    fn compute_max_size(&mut self) {
-        self.max_size = *self.seq_sizes.first().expect("Failed to get last element");
+    fn compute_avg_size(&mut self, seq_sizes: &[usize]) {
+        self.avg_size = seq_sizes
+            .iter()
+            .sum::<usize>()
+            .checked_div(self.number)
+            .unwrap_or(0);
     }
 
-    fn compute_avg_size(&mut self) {
-        self.avg_size = self.seq_sizes.iter().sum::<usize>() / self.number;
+    fn compute_sequenced_bases(&mut self) {
+        self.sequenced_bases = self.nucleotide_counts.iter().sum();
     }
 
     fn compute_number_n(&mut self) {
         self.number_n =
             self.nucleotide_counts[b'N' as usize] + self.nucleotide_counts[b'n' as usize];
-        self.percent_n = (self.number_n as f64 / self.cumul as f64) * 100.0;
+        self.percent_n = (self.number_n as f64 / self.sequenced_bases as f64) * 100.0;
     }
 
     fn compute_number_gc(&mut self) {
@@ -129,10 +457,18 @@ impl Metrics {
             + self.nucleotide_counts[b'C' as usize]
             + self.nucleotide_counts[b'g' as usize]
             + self.nucleotide_counts[b'c' as usize];
-        self.percent_gc = (self.number_gc as f64 / self.cumul as f64) * 100.0;
+        self.percent_gc = (self.number_gc as f64 / self.sequenced_bases as f64) * 100.0;
     }
 
-    fn compute_aun_and_nx_metrics(&mut self) {
+    /// Soft-masked bases are the lowercase ones, as written by RepeatMasker
+    fn compute_number_masked(&mut self) {
+        self.number_masked = self.nucleotide_counts[b'a' as usize..=b'z' as usize]
+            .iter()
+            .sum();
+        self.percent_masked = (self.number_masked as f64 / self.sequenced_bases as f64) * 100.0;
+    }
+
+    fn compute_aun_and_nx_metrics(&mut self, seq_sizes: &[usize]) {
         let breakpoints: Vec<usize> = vec![
             (0.5 * self.cumul as f64) as usize,
             (0.8 * self.cumul as f64) as usize,
@@ -151,7 +487,7 @@ impl Metrics {
         let mut current_lx_g = 0;
         let mut cumul: usize = 0;
 
-        for size in &self.seq_sizes {
+        for size in seq_sizes {
             cumul += *size;
             current_lx += 1;
             current_lx_g += 1;
@@ -201,21 +537,60 @@ impl Metrics {
         self.aun = (self.aun as f64 / self.cumul as f64) as usize;
     }
 
-    fn compute_mean_quality(&mut self) {
-        let mut mean_quality: f64 = 0.0;
-        for q in &self.mean_qualities {
-            mean_quality += q;
+    fn compute_mean_quality(&mut self, quality_sum: f64, quality_count: usize) {
+        self.mean_quality = (quality_sum / quality_count as f64) as usize;
+    }
+
+    fn compute_percent_short_pairs(&mut self) {
+        if self.pairs > 0 {
+            self.percent_short_pairs = (self.short_pairs as f64 / self.pairs as f64) * 100.0;
+        }
+    }
+
+    fn compute_pacbio_metrics(&mut self) {
+        if self.rq_reads > 0 {
+            self.percent_hifi_reads = (self.hifi_reads as f64 / self.number as f64) * 100.0;
+            self.percent_hifi_yield = (self.hifi_yield as f64 / self.cumul as f64) * 100.0;
+        }
+        if self.np_reads > 0 {
+            self.mean_passes = self.np_sum as f64 / self.np_reads as f64;
         }
-        mean_quality /= self.mean_qualities.len() as f64;
-        self.mean_quality = mean_quality as usize;
+    }
 
-        self.mean_qualities = Vec::new();
+    fn compute_protein_metrics(&mut self) {
+        self.protein = self.protein || looks_like_protein(&self.nucleotide_counts);
+        if self.protein && self.number > 0 {
+            self.percent_starts_with_m = (self.starts_with_m as f64 / self.number as f64) * 100.0;
+            self.percent_ends_with_stop = (self.ends_with_stop as f64 / self.number as f64) * 100.0;
+            self.internal_stops = self.nucleotide_counts[b'*' as usize] - self.ends_with_stop;
+        }
     }
 }
 
+// serde only implements arrays of up to 32 elements
+fn serialize_counts<S: Serializer>(
+    counts: &[usize; 256],
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    counts.as_slice().serialize(serializer)
+}
+
+fn deserialize_counts<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<[usize; 256], D::Error> {
+    Vec::<usize>::deserialize(deserializer)?
+        .try_into()
+        .map_err(|_| serde::de::Error::custom("expected 256 nucleotide counts"))
+}
+
 pub trait Num: Display {}
 impl Num for usize {}
 impl Num for f64 {}
+impl Num for String {}
+impl Num for &str {}
+
+// Value of the composition fields when only the sequence lengths are known
+const NOT_AVAILABLE: &str = "NA";
 
 impl Index<&str> for Metrics {
     type Output = dyn Num;
@@ -228,10 +603,18 @@ impl Index<&str> for Metrics {
             "max_size" => &self.max_size,
             "avg_size" => &self.avg_size,
             "aun" => &self.aun,
+            "number_n" | "percent_n" | "number_gc" | "percent_gc" | "number_masked"
+            | "percent_masked"
+                if !self.has_composition || self.protein =>
+            {
+                &NOT_AVAILABLE
+            }
             "number_n" => &self.number_n,
             "percent_n" => &self.percent_n,
             "number_gc" => &self.number_gc,
             "percent_gc" => &self.percent_gc,
+            "number_masked" => &self.number_masked,
+            "percent_masked" => &self.percent_masked,
             "n50" => &self.n50,
             "l50" => &self.l50,
             "n80" => &self.n80,
@@ -245,7 +628,100 @@ impl Index<&str> for Metrics {
             "ng90" => &self.ng90,
             "lg90" => &self.lg90,
             "mean_quality" => &self.mean_quality,
-            _ => panic!("Unknown field: {index}"),
+            "checksum" => &self.checksum_digest,
+            "pairs" => &self.pairs,
+            "short_pairs" => &self.short_pairs,
+            "percent_short_pairs" => &self.percent_short_pairs,
+            "secondary" => &self.secondary,
+            "supplementary" => &self.supplementary,
+            "rq_below_q10" => &self.rq_bins[0],
+            "rq_q10_q19" => &self.rq_bins[1],
+            "rq_q20_q29" => &self.rq_bins[2],
+            "rq_q30_q39" => &self.rq_bins[3],
+            "rq_q40_above" => &self.rq_bins[4],
+            "hifi_reads" => &self.hifi_reads,
+            "percent_hifi_reads" => &self.percent_hifi_reads,
+            "hifi_yield" => &self.hifi_yield,
+            "percent_hifi_yield" => &self.percent_hifi_yield,
+            "mean_passes" => &self.mean_passes,
+            "group" => &self.group,
+            "run_ids"
+            | "flow_cells"
+            | "active_channels"
+            | "min_reads_per_channel"
+            | "median_reads_per_channel"
+            | "max_reads_per_channel" => match &self.nanopore_summary {
+                None => &NOT_AVAILABLE,
+                Some(summary) => match index {
+                    "run_ids" => &summary.run_ids,
+                    "flow_cells" => &summary.flow_cells,
+                    "active_channels" => &summary.active_channels,
+                    "min_reads_per_channel" => &summary.reads_per_channel[0],
+                    "median_reads_per_channel" => &summary.reads_per_channel[1],
+                    _ => &summary.reads_per_channel[2],
+                },
+            },
+            "links" => &self.links,
+            "dead_ends" => &self.dead_ends,
+            "components" => &self.components,
+            "starts_with_m" => &self.starts_with_m,
+            "percent_starts_with_m" => &self.percent_starts_with_m,
+            "ends_with_stop" => &self.ends_with_stop,
+            "percent_ends_with_stop" => &self.percent_ends_with_stop,
+            "internal_stops" => &self.internal_stops,
+            "total_reads" => self
+                .sampling
+                .as_ref()
+                .map_or(&NOT_AVAILABLE, |s| &s.total_reads),
+            "total_bases" => self
+                .sampling
+                .as_ref()
+                .map_or(&NOT_AVAILABLE, |s| &s.total_bases),
+            "estimated_totals" => match &self.sampling {
+                Some(sampling) if sampling.extrapolated => &"true",
+                Some(_) => &"false",
+                None => &NOT_AVAILABLE,
+            },
+            _ => {
+                // removed_reads_<filter> and removed_bases_<filter>
+                let removed = |prefix| {
+                    let filter = index.strip_prefix(prefix)?;
+                    Filter::ALL.iter().position(|f| f.field() == filter)
+                };
+                if let Some(i) = removed("removed_reads_") {
+                    &self.removed[i].0
+                } else if let Some(i) = removed("removed_bases_") {
+                    &self.removed[i].1
+                } else {
+                    panic!("Unknown field: {index}")
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_inferred_name() {
+        let name = |path| MetricsBuilder::new(path, 0, None).filename;
+        assert_eq!(name("data/reads.fastq.gz"), "reads");
+        assert_eq!(name("my.fasta_sample.fa"), "my.fasta_sample");
+        assert_eq!(name("assembly.v2.fna"), "assembly.v2");
+        assert_eq!(name("contigs.fas.gz"), "contigs");
+        assert_eq!(name("reads.txt"), "reads.txt");
+    }
+
+    #[test]
+    fn test_rq_bins() {
+        let mut builder = MetricsBuilder::new("hifi.bam", 0, None);
+        for rq in [0.8999, 0.9, 0.99, 0.999, 0.9999, 1.0] {
+            builder.add_pacbio_tags((Some(rq), None), 100);
         }
+        assert_eq!(builder.rq_bins, [1, 1, 1, 1, 2]);
+        assert_eq!(builder.hifi_reads, 4);
+        assert_eq!(builder.hifi_yield, 400);
     }
 }
@@ -0,0 +1,254 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Fields of the `key=value` description of Nanopore read headers
+#[derive(Debug, Default)]
+pub struct NanoporeHeader<'a> {
+    pub run_id: Option<&'a str>,
+    pub channel: Option<u32>,
+    pub start_time: Option<i64>,
+    pub barcode: Option<&'a str>,
+    pub flow_cell_id: Option<&'a str>,
+}
+
+pub fn parse_header(id: &[u8]) -> NanoporeHeader<'_> {
+    let mut header = NanoporeHeader::default();
+    let Ok(id) = std::str::from_utf8(id) else {
+        return header;
+    };
+
+    for token in id.split_ascii_whitespace().skip(1) {
+        if let Some((key, value)) = token.split_once('=') {
+            match key {
+                "runid" => header.run_id = Some(value),
+                "ch" => header.channel = value.parse().ok(),
+                "start_time" => header.start_time = parse_timestamp(value),
+                "barcode" => header.barcode = Some(value),
+                "flow_cell_id" => header.flow_cell_id = Some(value),
+                _ => {}
+            }
+        }
+    }
+
+    header
+}
+
+/// Yield over time and channel activity of a Nanopore run
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct NanoporeStats {
+    /// Reads and bases for each hour since the epoch
+    pub hourly: BTreeMap<i64, (usize, usize)>,
+    /// Reads and bases for each channel
+    pub channels: BTreeMap<u32, (usize, usize)>,
+    pub run_ids: BTreeSet<String>,
+    pub flow_cells: BTreeSet<String>,
+}
+
+impl NanoporeStats {
+    pub(crate) fn add_record(&mut self, header: &NanoporeHeader, record_len: usize) {
+        if let Some(start_time) = header.start_time {
+            let hour = self.hourly.entry(start_time.div_euclid(3600)).or_default();
+            hour.0 += 1;
+            hour.1 += record_len;
+        }
+        if let Some(channel) = header.channel {
+            let channel = self.channels.entry(channel).or_default();
+            channel.0 += 1;
+            channel.1 += record_len;
+        }
+        if let Some(run_id) = header.run_id {
+            if !self.run_ids.contains(run_id) {
+                self.run_ids.insert(run_id.to_string());
+            }
+        }
+        if let Some(flow_cell_id) = header.flow_cell_id {
+            if !self.flow_cells.contains(flow_cell_id) {
+                self.flow_cells.insert(flow_cell_id.to_string());
+            }
+        }
+    }
+
+    pub fn merge(&mut self, other: NanoporeStats) {
+        for (hour, (reads, bases)) in other.hourly {
+            let hour = self.hourly.entry(hour).or_default();
+            hour.0 += reads;
+            hour.1 += bases;
+        }
+        for (channel, (reads, bases)) in other.channels {
+            let channel = self.channels.entry(channel).or_default();
+            channel.0 += reads;
+            channel.1 += bases;
+        }
+        self.run_ids.extend(other.run_ids);
+        self.flow_cells.extend(other.flow_cells);
+    }
+
+    /// Minimum, median and maximum number of reads per active channel
+    pub fn reads_per_channel(&self) -> (usize, usize, usize) {
+        let mut reads = self
+            .channels
+            .values()
+            .map(|(reads, _)| *reads)
+            .collect::<Vec<usize>>();
+        if reads.is_empty() {
+            return (0, 0, 0);
+        }
+        reads.sort_unstable();
+
+        (reads[0], reads[reads.len() / 2], reads[reads.len() - 1])
+    }
+
+    pub fn summary(&self) -> NanoporeSummary {
+        let (min, median, max) = self.reads_per_channel();
+        NanoporeSummary {
+            run_ids: self.run_ids.len(),
+            flow_cells: self
+                .flow_cells
+                .iter()
+                .cloned()
+                .collect::<Vec<String>>()
+                .join(";"),
+            active_channels: self.channels.len(),
+            reads_per_channel: [min, median, max],
+        }
+    }
+}
+
+/// Run metrics of a Nanopore input, as output in the CSV and parsable modes
+#[derive(Debug, Clone)]
+pub struct NanoporeSummary {
+    pub run_ids: usize,
+    /// Flow cell ids, separated by semicolons
+    pub flow_cells: String,
+    pub active_channels: usize,
+    /// Minimum, median and maximum number of reads per active channel
+    pub reads_per_channel: [usize; 3],
+}
+
+/// Reads and bases of several inputs for each hour since the earliest read of
+/// any of them. Hours without reads in any input are skipped, so that runs
+/// days apart do not print empty hours.
+pub fn yield_per_hour(stats: &[&NanoporeStats]) -> Vec<(i64, Vec<(usize, usize)>)> {
+    let hours = stats
+        .iter()
+        .flat_map(|s| s.hourly.keys().copied())
+        .collect::<BTreeSet<i64>>();
+    let Some(&first) = hours.first() else {
+        return Vec::new();
+    };
+
+    hours
+        .into_iter()
+        .map(|hour| {
+            let yields = stats
+                .iter()
+                .map(|s| s.hourly.get(&hour).copied().unwrap_or_default())
+                .collect();
+            (hour - first, yields)
+        })
+        .collect()
+}
+
+/// Converts an ISO 8601 date such as 2023-05-04T12:34:56.789+02:00 to
+/// seconds since the epoch
+fn parse_timestamp(value: &str) -> Option<i64> {
+    let (date, time) = value.split_once('T')?;
+
+    let mut date_fields = date.splitn(3, '-');
+    let year: i64 = date_fields.next()?.parse().ok()?;
+    let month: i64 = date_fields.next()?.parse().ok()?;
+    let day: i64 = date_fields.next()?.parse().ok()?;
+
+    // Split the time zone designator from the time
+    let (time, offset) = match time.find(['Z', '+', '-']) {
+        Some(i) => (&time[..i], &time[i..]),
+        None => (time, ""),
+    };
+    let mut time_fields = time.splitn(3, ':');
+    let hours: i64 = time_fields.next()?.parse().ok()?;
+    let minutes: i64 = time_fields.next()?.parse().ok()?;
+    let seconds: f64 = time_fields.next()?.parse().ok()?;
+
+    let offset_seconds = match offset.split_at_checked(1) {
+        Some((sign @ ("+" | "-"), hh_mm)) => {
+            let (hh, mm) = hh_mm
+                .split_once(':')
+                .or_else(|| hh_mm.split_at_checked(2))?;
+            let seconds = hh.parse::<i64>().ok()? * 3600 + mm.parse::<i64>().ok()? * 60;
+            if sign == "-" {
+                -seconds
+            } else {
+                seconds
+            }
+        }
+        _ => 0,
+    };
+
+    Some(
+        days_from_civil(year, month, day) * 86400 + hours * 3600 + minutes * 60 + seconds as i64
+            - offset_seconds,
+    )
+}
+
+/// Number of days between 1970-01-01 and the given date of the Gregorian calendar
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+
+    era * 146097 + day_of_era - 719468
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_header() {
+        let header = parse_header(b"0a1b2c3d runid=abc123 sampleid=s1 read=12 ch=245 start_time=2023-05-04T12:34:56Z flow_cell_id=FAX12345 barcode=barcode07");
+        assert_eq!(header.run_id, Some("abc123"));
+        assert_eq!(header.channel, Some(245));
+        assert_eq!(header.start_time, Some(1683203696));
+        assert_eq!(header.barcode, Some("barcode07"));
+        assert_eq!(header.flow_cell_id, Some("FAX12345"));
+    }
+
+    #[test]
+    fn test_parse_timestamp() {
+        assert_eq!(parse_timestamp("1970-01-01T00:00:00Z"), Some(0));
+        assert_eq!(
+            parse_timestamp("2023-05-04T14:34:56.789+02:00"),
+            Some(1683203696)
+        );
+        assert_eq!(
+            parse_timestamp("2023-05-04T07:34:56-05:00"),
+            Some(1683203696)
+        );
+        assert_eq!(parse_timestamp("yesterday"), None);
+    }
+
+    #[test]
+    fn test_yield_per_hour() {
+        let header = |start_time| NanoporeHeader {
+            start_time: Some(start_time),
+            ..Default::default()
+        };
+        let mut first = NanoporeStats::default();
+        first.add_record(&header(3600 * 10 + 5), 100);
+        first.add_record(&header(3600 * 11), 200);
+        let mut second = NanoporeStats::default();
+        second.add_record(&header(3600 * 11 + 30), 300);
+        second.add_record(&header(3600 * 48), 400);
+
+        assert_eq!(
+            yield_per_hour(&[&first, &second]),
+            [
+                (0, vec![(1, 100), (0, 0)]),
+                (1, vec![(1, 200), (1, 300)]),
+                (38, vec![(0, 0), (1, 400)]),
+            ]
+        );
+    }
+}
@@ -0,0 +1,267 @@
+use crate::gfa::GraphStats;
+use crate::metrics::MetricsBuilder;
+use crate::parser::{new_builder, Config};
+use crate::reader::get_reader;
+use std::path::{Path, PathBuf};
+
+/// Metrics of both mates of a paired-end library, along with pair counts
+struct PairAccumulator<'a> {
+    config: &'a Config,
+    r1_builder: MetricsBuilder,
+    r2_builder: MetricsBuilder,
+    pairs: usize,
+    short_pairs: usize,
+}
+
+impl<'a> PairAccumulator<'a> {
+    fn new(r1_builder: MetricsBuilder, r2_builder: MetricsBuilder, config: &'a Config) -> Self {
+        PairAccumulator {
+            config,
+            r1_builder,
+            r2_builder,
+            pairs: 0,
+            short_pairs: 0,
+        }
+    }
+
+    fn add_pair(&mut self, r1: Mate, r2: Mate) {
+        if mate_id(r1.id) != mate_id(r2.id) {
+            panic!(
+                "Read ids do not match in pair {}: {} and {}",
+                self.pairs + 1,
+                String::from_utf8_lossy(r1.id),
+                String::from_utf8_lossy(r2.id)
+            );
+        }
+        self.pairs += 1;
+
+        let min_size = self.config.min_size;
+        if r1.seq.len() < min_size || r2.seq.len() < min_size {
+            self.short_pairs += 1;
+        }
+
+        if r1.seq.len() >= min_size {
+            self.r1_builder.add_record(r1.seq, r1.qual);
+        }
+        if r2.seq.len() >= min_size {
+            self.r2_builder.add_record(r2.seq, r2.qual);
+        }
+    }
+
+    fn finish(mut self) -> (MetricsBuilder, MetricsBuilder) {
+        for builder in [&mut self.r1_builder, &mut self.r2_builder] {
+            builder.pairs = self.pairs;
+            builder.short_pairs = self.short_pairs;
+        }
+
+        (self.r1_builder, self.r2_builder)
+    }
+}
+
+struct Mate<'a> {
+    id: &'a [u8],
+    seq: &'a [u8],
+    qual: Option<&'a [u8]>,
+}
+
+/// Accumulates the records of each mate of a paired-end library, checking
+/// that both files list the same read ids in the same order.
+pub fn build_paired_stats(
+    r1_path: &Path,
+    r2_path: &Path,
+    config: &Config,
+) -> (MetricsBuilder, MetricsBuilder) {
+    let mut r1_reader = get_reader(r1_path);
+    let mut r2_reader = get_reader(r2_path);
+    r1_reader.set_qual_offset(config.qual_offset);
+    r2_reader.set_qual_offset(config.qual_offset);
+
+    let mut accumulator = PairAccumulator::new(
+        paired_builder(r1_path, config, None),
+        paired_builder(r2_path, config, None),
+        config,
+    );
+
+    loop {
+        let (r1, r2) = match (r1_reader.next_record(), r2_reader.next_record()) {
+            (None, None) => break,
+            (Some(r1), Some(r2)) => (r1, r2),
+            _ => panic!(
+                "{r1_path:?} and {r2_path:?} do not have the same number of records (mismatch after {} pairs)",
+                accumulator.pairs
+            ),
+        };
+
+        let (r1_seq, r2_seq) = (r1.seq(), r2.seq());
+        accumulator.add_pair(
+            Mate {
+                id: r1.id(),
+                seq: &r1_seq,
+                qual: r1.qual(),
+            },
+            Mate {
+                id: r2.id(),
+                seq: &r2_seq,
+                qual: r2.qual(),
+            },
+        );
+    }
+
+    let (mut r1_builder, mut r2_builder) = accumulator.finish();
+    for (builder, reader, path) in [
+        (&mut r1_builder, &r1_reader, r1_path),
+        (&mut r2_builder, &r2_reader, r2_path),
+    ] {
+        if let Some(graph_stats) = reader.graph_stats(path) {
+            graph_stats.add_to(builder);
+        }
+    }
+
+    (r1_builder, r2_builder)
+}
+
+/// Same as `build_paired_stats` for files where each R1 record is directly
+/// followed by its R2 mate.
+pub fn build_interleaved_stats(
+    file_paths: &[PathBuf],
+    config: &Config,
+    name: Option<String>,
+) -> (MetricsBuilder, MetricsBuilder) {
+    let mut r1_builder = paired_builder(&file_paths[0], config, name);
+    let mut r2_builder = paired_builder(&file_paths[0], config, None);
+    r2_builder.filename = format!("{} R2", r1_builder.filename);
+    r1_builder.filename.push_str(" R1");
+
+    let mut accumulator = PairAccumulator::new(r1_builder, r2_builder, config);
+    let mut graph_stats = GraphStats::default();
+
+    for file_path in file_paths {
+        let mut reader = get_reader(file_path);
+        reader.set_qual_offset(config.qual_offset);
+
+        // The first mate is copied as the reader reuses its buffer for the next record
+        while let Some(r1) = reader.next_record() {
+            let r1_id = r1.id().to_vec();
+            let r1_seq = r1.seq().into_owned();
+            let r1_qual = r1.qual().map(|q| q.to_vec());
+
+            let r2 = match reader.next_record() {
+                Some(r2) => r2,
+                None => panic!(
+                    "{file_path:?} holds an odd number of records, the last R1 ({}) has no mate",
+                    String::from_utf8_lossy(&r1_id)
+                ),
+            };
+
+            let r2_seq = r2.seq();
+            accumulator.add_pair(
+                Mate {
+                    id: &r1_id,
+                    seq: &r1_seq,
+                    qual: r1_qual.as_deref(),
+                },
+                Mate {
+                    id: r2.id(),
+                    seq: &r2_seq,
+                    qual: r2.qual(),
+                },
+            );
+        }
+
+        if let Some(file_graph_stats) = reader.graph_stats(file_path) {
+            graph_stats.merge(&file_graph_stats);
+        }
+    }
+
+    // Both mates are segments of the same graph
+    let (mut r1_builder, mut r2_builder) = accumulator.finish();
+    graph_stats.add_to(&mut r1_builder);
+    graph_stats.add_to(&mut r2_builder);
+
+    (r1_builder, r2_builder)
+}
+
+/// Builder of a mate, which only supports the checksum of the optional metrics
+fn paired_builder(file_path: &Path, config: &Config, name: Option<String>) -> MetricsBuilder {
+    let mut builder = new_builder(file_path, config, name);
+    builder.nanopore = None;
+    builder.illumina = None;
+    builder.protein = false;
+    builder
+}
+
+/// Read id without its description and without the /1 or /2 mate suffix
+pub(crate) fn mate_id(id: &[u8]) -> &[u8] {
+    let id = id
+        .split(|c| c.is_ascii_whitespace())
+        .next()
+        .unwrap_or_default();
+
+    match id {
+        [start @ .., b'/', b'1' | b'2'] => start,
+        _ => id,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_config(min_size: usize) -> Config {
+        Config {
+            min_size,
+            qual_offset: 33,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_mate_id() {
+        assert_eq!(mate_id(b"read_1/1"), b"read_1");
+        assert_eq!(mate_id(b"read_1/2 length=150"), b"read_1");
+        assert_eq!(
+            mate_id(b"A00123:8:H7KJNDSXX:1:1101:1000:2000 1:N:0:ACGT"),
+            b"A00123:8:H7KJNDSXX:1:1101:1000:2000"
+        );
+    }
+
+    #[test]
+    fn test_paired_stats() {
+        let (r1, r2) = build_paired_stats(
+            Path::new("test_inputs/pairs_R1.fastq.gz"),
+            Path::new("test_inputs/pairs_R2.fastq.gz"),
+            &test_config(100),
+        );
+        let (r1, r2) = (r1.compute(), r2.compute());
+        assert_eq!(r1.pairs, 100);
+        assert_eq!(r1.short_pairs, 23);
+        assert_eq!(r1.number, 90);
+        assert_eq!(r2.number, 85);
+        assert_eq!(r2.cumul, 12750);
+    }
+
+    #[test]
+    fn test_interleaved_stats() {
+        let (r1, r2) = build_interleaved_stats(
+            &[PathBuf::from("test_inputs/interleaved.fastq.gz")],
+            &test_config(100),
+            None,
+        );
+        let (r1, r2) = (r1.compute(), r2.compute());
+        assert_eq!(r1.filename, "interleaved R1");
+        assert_eq!(r1.pairs, 100);
+        assert_eq!(r2.short_pairs, 23);
+        assert_eq!(r1.number, 90);
+        assert_eq!(r2.cumul, 12750);
+    }
+
+    #[test]
+    #[should_panic(expected = "Read ids do not match")]
+    fn test_paired_stats_mismatched_ids() {
+        build_paired_stats(
+            Path::new("test_inputs/pairs_R1.fastq.gz"),
+            Path::new("test_inputs/reads.fastq.gz"),
+            &test_config(0),
+        );
+    }
+}
@@ -1,171 +1,475 @@
-use crate::report::print;
-use crate::{metrics::Metrics, report::print_csv, report::print_parsable};
-use flate2::read::GzDecoder;
-use needletail::parser::SequenceRecord;
+use crate::checksum::{Checksum, ChecksumOptions};
+use crate::fai::build_fai_stats;
+use crate::filter::failed_filter;
+use crate::gfa::GraphStats;
+use crate::illumina::{self, IlluminaStats};
+use crate::inputs::Input;
+use crate::metrics::{Metrics, MetricsBuilder};
+use crate::nanopore::{self, NanoporeStats};
+use crate::paired::{build_interleaved_stats, build_paired_stats};
+use crate::reader::{get_counting_reader, Reader, Record};
+use crate::sample::{SampleMode, Sampler, Sampling};
+use crate::writer::{create_output, write_record};
+use std::collections::BTreeMap;
 use std::io::{BufWriter, Write};
 use std::path::{Path, PathBuf};
 
-pub fn parse(
-    files: &[PathBuf],
-    min_size: usize,
-    genome_size: i64,
-    qual_offset: u8,
-    parsable: bool,
-    csv: bool,
-    per_seq: Option<PathBuf>,
-    rename: Option<String>,
-    output_fields: Option<Vec<String>>,
-    no_header: bool,
-) {
-    let mut per_seq_writer: Option<BufWriter<std::fs::File>> = None;
-    if let Some(path) = per_seq {
+#[derive(Default)]
+pub struct Config {
+    pub min_size: usize,
+    pub max_size: Option<usize>,
+    pub min_qual: Option<f64>,
+    pub max_n_frac: Option<f64>,
+    pub min_gc: Option<f64>,
+    pub max_gc: Option<f64>,
+    pub sample: Option<SampleMode>,
+    pub seed: u64,
+    pub output: Option<PathBuf>,
+    pub genome_size: i64,
+    pub qual_offset: u8,
+    pub parsable: bool,
+    pub csv: bool,
+    pub per_seq: Option<PathBuf>,
+    pub output_fields: Option<Vec<String>>,
+    pub no_header: bool,
+    pub checksum: Option<ChecksumOptions>,
+    pub paired: Option<(PathBuf, PathBuf)>,
+    pub interleaved: bool,
+    pub nanopore: bool,
+    pub illumina: bool,
+    pub group_by: Option<GroupBy>,
+    pub fai: bool,
+    pub composition: bool,
+    pub non_nucleotide: Option<NonNucleotide>,
+    pub protein: bool,
+}
+
+/// Header field used to split the records of an input into several columns
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum GroupBy {
+    /// Nanopore barcode
+    Barcode,
+    /// Nanopore run id
+    Runid,
+    /// Illumina lane
+    Lane,
+    /// Illumina tile
+    Tile,
+    /// Illumina index sequence
+    Index,
+}
+
+/// What to do when a sequence holds characters other than nucleotides
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum NonNucleotide {
+    /// Print a warning on stderr
+    Warn,
+    /// Stop with an error
+    Fail,
+}
+
+impl GroupBy {
+    fn key(self, id: &[u8]) -> &str {
+        match self {
+            GroupBy::Barcode => nanopore::parse_header(id).barcode.unwrap_or("unclassified"),
+            GroupBy::Runid => nanopore::parse_header(id).run_id.unwrap_or("unknown"),
+            GroupBy::Lane => illumina::parse_header(id).map_or("unknown", |h| h.lane),
+            GroupBy::Tile => illumina::parse_header(id).map_or("unknown", |h| h.tile),
+            GroupBy::Index => illumina::parse_header(id)
+                .and_then(|h| h.index)
+                .unwrap_or("unknown"),
+        }
+    }
+
+    /// Prefix of the group in the column names
+    fn label(self) -> &'static str {
+        match self {
+            GroupBy::Lane => "lane ",
+            GroupBy::Tile => "tile ",
+            _ => "",
+        }
+    }
+}
+
+/// Computes the metrics of each input
+pub fn parse(inputs: &[Input], config: &Config) -> Vec<Metrics> {
+    build(inputs, config)
+        .into_iter()
+        .map(MetricsBuilder::compute)
+        .collect()
+}
+
+/// Accumulates the records of each input, without computing the metrics
+pub fn build(inputs: &[Input], config: &Config) -> Vec<MetricsBuilder> {
+    let mut per_seq_writer: Option<Box<dyn Write>> = None;
+    if let Some(path) = &config.per_seq {
         let file =
             std::fs::File::create(path).unwrap_or_else(|e| panic!("Failed to create file: {e}"));
-        per_seq_writer = Some(BufWriter::new(file));
+        per_seq_writer = Some(Box::new(BufWriter::new(file)));
     }
 
-    let splits = rename.map(|names| {
-        names
-            .split(',')
-            .map(|s| s.to_string())
-            .collect::<Vec<String>>()
-    });
+    let builders = build_with_per_seq(inputs, config, &mut per_seq_writer);
+    if let Some(writer) = per_seq_writer.as_mut() {
+        writer
+            .flush()
+            .unwrap_or_else(|e| panic!("Failed to write the per-sequence metrics: {e}"));
+    }
+    builders
+}
 
-    let mut metrics_vec = Vec::new();
-    for (i, f) in files.iter().enumerate() {
-        let name = splits.as_ref().map(|names| names[i].clone());
+/// Same as `build`, writing the per-sequence metrics to the given writer
+pub fn build_with_per_seq(
+    inputs: &[Input],
+    config: &Config,
+    per_seq_writer: &mut Option<Box<dyn Write>>,
+) -> Vec<MetricsBuilder> {
+    let mut output = config.output.as_deref().map(create_output);
+
+    let mut builders = Vec::new();
+    if let Some((r1_path, r2_path)) = &config.paired {
+        let (r1_builder, r2_builder) = build_paired_stats(r1_path, r2_path, config);
+        builders.push(r1_builder);
+        builders.push(r2_builder);
+    }
 
-        metrics_vec.push(compute_stats(
-            f,
-            min_size,
-            genome_size,
-            qual_offset,
-            &mut per_seq_writer,
-            name,
-        ));
+    for input in inputs {
+        let name = input.name.clone();
+
+        if config.fai {
+            builders.push(build_fai_stats(&input.paths, config, name));
+        } else if let Some(group_by) = config.group_by {
+            builders.extend(build_grouped_stats(
+                &input.paths,
+                config,
+                group_by,
+                per_seq_writer,
+                name,
+            ));
+        } else if let Some(output) = output.as_mut() {
+            let (before, after) =
+                build_filtered_stats(&input.paths, config, output, per_seq_writer, name);
+            builders.push(before);
+            builders.push(after);
+        } else if config.interleaved {
+            let (r1_builder, r2_builder) = build_interleaved_stats(&input.paths, config, name);
+            builders.push(r1_builder);
+            builders.push(r2_builder);
+        } else {
+            builders.push(build_stats(&input.paths, config, per_seq_writer, name));
+        }
     }
 
-    if csv {
-        print_csv(&metrics_vec);
-    } else if parsable {
-        print_parsable(&metrics_vec, &output_fields, no_header);
-    } else {
-        print(&metrics_vec);
+    if let Some(output) = output {
+        output.finish();
     }
+    builders
 }
 
-fn compute_stats(
-    file_path: &Path,
-    min_size: usize,
-    genome_size: i64,
-    qual_offset: u8,
-    per_seq_writer: &mut Option<BufWriter<std::fs::File>>,
+#[cfg(test)]
+pub fn compute_stats(
+    file_paths: &[PathBuf],
+    config: &Config,
+    per_seq_writer: &mut Option<Box<dyn Write>>,
     name: Option<String>,
 ) -> Metrics {
-    let mut reader = get_reader(file_path);
-    let mut metrics = Metrics::new(file_path.to_str().unwrap(), genome_size, name);
+    build_stats(file_paths, config, per_seq_writer, name).compute()
+}
+
+pub fn build_stats(
+    file_paths: &[PathBuf],
+    config: &Config,
+    per_seq_writer: &mut Option<Box<dyn Write>>,
+    name: Option<String>,
+) -> MetricsBuilder {
+    let mut builder = new_builder(&file_paths[0], config, name);
 
-    while let Some(record) = reader.next() {
-        let record = record.expect("Error");
-        let record_len = record.seq().len();
+    let counts = read_records(file_paths, config, |record| {
+        process_record(&mut builder, record, config, per_seq_writer);
+    });
+    counts.add_to(&mut builder);
 
-        if record_len < min_size {
-            continue;
+    builder
+}
+
+/// Same as `build_stats`, with the builders of all the records and of the
+/// records passing the filters, which are written to the output
+pub fn build_filtered_stats(
+    file_paths: &[PathBuf],
+    config: &Config,
+    output: &mut dyn Write,
+    per_seq_writer: &mut Option<Box<dyn Write>>,
+    name: Option<String>,
+) -> (MetricsBuilder, MetricsBuilder) {
+    let basename = new_builder(&file_paths[0], config, name).filename;
+    let mut before = new_builder(
+        &file_paths[0],
+        config,
+        Some(format!("{basename} (before filters)")),
+    );
+    let mut after = new_builder(
+        &file_paths[0],
+        config,
+        Some(format!("{basename} (after filters)")),
+    );
+
+    let counts = read_records(file_paths, config, |record| {
+        add_record(&mut before, record, &mut None);
+        if let Some(filter) = failed_filter(record, config) {
+            after.add_removed(filter, record.len());
+        } else {
+            add_record(&mut after, record, per_seq_writer);
+            // Only the length of GFA segments without sequence is known
+            if record.seq().len() == record.len() {
+                write_record(output, record)
+                    .unwrap_or_else(|e| panic!("Failed to write records: {e}"));
+            }
         }
+    });
+    after.sampling.clone_from(&counts.sampling);
+    counts.add_to(&mut before);
 
-        metrics.seq_sizes.push(record_len);
-        count_nucleotides(&mut metrics, &record.seq());
-        let avg_quality = compute_avg_quality(&mut metrics, record.qual(), qual_offset);
-        write_per_seq(record, per_seq_writer, avg_quality, record_len);
-    }
+    (before, after)
+}
 
-    metrics.compute();
-    metrics
+/// What the readers of an input report besides its records
+#[derive(Debug, Default, Clone)]
+pub struct ReaderCounts {
+    pub secondary: usize,
+    pub supplementary: usize,
+    pub graph_stats: GraphStats,
+    pub sampling: Option<Sampling>,
 }
 
-fn count_nucleotides(metrics: &mut Metrics, seq: &[u8]) {
-    for c in seq.iter() {
-        metrics.nucleotide_counts[*c as usize] += 1;
+impl ReaderCounts {
+    pub fn add_to(self, builder: &mut MetricsBuilder) {
+        builder.secondary += self.secondary;
+        builder.supplementary += self.supplementary;
+        self.graph_stats.add_to(builder);
+        builder.sampling = self.sampling;
     }
 }
 
-fn compute_avg_quality(metrics: &mut Metrics, qualities: Option<&[u8]>, qual_offset: u8) -> f64 {
-    let mut sum_quality: f32 = 0.0;
-    let mut avg_quality: f64 = 0.0;
-    let mut power_values = [0.0f32; 256];
+/// Calls `f` on each record of the files of an input, or only on the
+/// records sampled with the sampling mode of the config
+pub fn read_records(
+    file_paths: &[PathBuf],
+    config: &Config,
+    mut f: impl FnMut(&Record),
+) -> ReaderCounts {
+    let mut counts = ReaderCounts::default();
+    let mut sampler = config.sample.map(|mode| Sampler::new(mode, config.seed));
+    // Bytes of the files holding the records read, to extrapolate the totals
+    // when stopping early
+    let mut bytes_read = 0;
+    let mut stopped = false;
+    let mut graph_skipped = false;
+
+    for file_path in file_paths {
+        let (mut reader, progress) = get_counting_reader(file_path);
+        reader.set_qual_offset(config.qual_offset);
+        let mut stream_bytes = 0;
+
+        while let Some(record) = reader.next_record() {
+            // Checked before the record, so that it is only read when needed
+            if sampler.as_ref().is_some_and(|sampler| {
+                sampler.is_done(bytes_read + progress.file_bytes(stream_bytes))
+            }) {
+                stopped = true;
+                break;
+            }
+            stream_bytes += record.input_bytes();
+
+            if sampler
+                .as_mut()
+                .is_none_or(|sampler| sampler.sample(&record))
+            {
+                f(&record);
+            }
+        }
+        bytes_read += progress.file_bytes(stream_bytes);
 
-    if let Some(qualities) = qualities {
-        let len = qualities.len() as f64;
-        let offset = qual_offset as f32;
+        let (secondary, supplementary) = reader.skipped_records();
+        counts.secondary += secondary;
+        counts.supplementary += supplementary;
 
-        for q in qualities.iter().map(|q| *q as usize) {
-            if power_values[q] == 0.0 {
-                power_values[q] = 1.0 / 10.0f32.powf((q as f32 - offset) / 10.0);
+        // Links of a partly read graph may name segments that were not read
+        if stopped {
+            if matches!(reader, Reader::Gfa(_)) {
+                graph_skipped = true;
             }
-            sum_quality += power_values[q];
+            break;
+        }
+        if let Some(graph_stats) = reader.graph_stats(file_path) {
+            counts.graph_stats.merge(&graph_stats);
+        }
+    }
+
+    if let Some(sampler) = sampler {
+        let mut sampling = sampler.sampling();
+        let total_bytes = file_paths
+            .iter()
+            .map(|path| std::fs::metadata(path).map_or(0, |m| m.len()))
+            .sum();
+        // Nothing can be extrapolated from 2bit files, whose bytes are not counted
+        if stopped && bytes_read > 0 {
+            sampling.extrapolate(bytes_read, total_bytes);
+            sampling
+                .description
+                .push_str(", totals estimated from the bytes read");
+        } else if stopped {
+            sampling
+                .description
+                .push_str(", totals of the records read");
+        }
+        if graph_skipped {
+            counts.graph_stats = GraphStats::default();
+            sampling
+                .description
+                .push_str(", graph metrics not computed");
         }
-        avg_quality = -10.0 * (sum_quality as f64 / len).log10();
-        metrics.mean_qualities.push(avg_quality);
+        counts.sampling = Some(sampling);
+        for record in sampler.into_reservoir() {
+            f(&Record::Sampled(&record));
+        }
+    }
+
+    counts
+}
+
+/// Same as `build_stats`, with one builder for each group of records
+/// sharing the same header field (barcode, run id...)
+pub fn build_grouped_stats(
+    file_paths: &[PathBuf],
+    config: &Config,
+    group_by: GroupBy,
+    per_seq_writer: &mut Option<Box<dyn Write>>,
+    name: Option<String>,
+) -> Vec<MetricsBuilder> {
+    let basename = new_builder(&file_paths[0], config, name).filename;
+    let mut groups: BTreeMap<String, MetricsBuilder> = BTreeMap::new();
+
+    let counts = read_records(file_paths, config, |record| {
+        let group = group_by.key(record.id());
+        if !groups.contains_key(group) {
+            let name = format!("{basename} {}{group}", group_by.label());
+            let mut builder = new_builder(&file_paths[0], config, Some(name));
+            builder.group = group.to_string();
+            groups.insert(group.to_string(), builder);
+        }
+
+        let builder = groups.get_mut(group).unwrap();
+        process_record(builder, record, config, per_seq_writer);
+    });
+
+    // Skipped alignments, the graph and the sampling cannot be split by
+    // group, so each group gets the counts of the whole input
+    for builder in groups.values_mut() {
+        counts.clone().add_to(builder);
     }
 
-    avg_quality
+    groups.into_values().collect()
+}
+
+/// Metrics builder of an input, with the optional metrics enabled by the config
+pub fn new_builder(file_path: &Path, config: &Config, name: Option<String>) -> MetricsBuilder {
+    let mut builder = MetricsBuilder::new(file_path.to_str().unwrap(), config.genome_size, name);
+    builder.qual_offset = config.qual_offset;
+    builder.checksum = config.checksum.map(Checksum::new);
+    builder.protein = config.protein;
+    if config.nanopore {
+        builder.nanopore = Some(NanoporeStats::default());
+    }
+    if config.illumina {
+        builder.illumina = Some(IlluminaStats::default());
+    }
+    builder
+}
+
+fn process_record(
+    builder: &mut MetricsBuilder,
+    record: &Record,
+    config: &Config,
+    per_seq_writer: &mut Option<Box<dyn Write>>,
+) {
+    match failed_filter(record, config) {
+        Some(filter) => builder.add_removed(filter, record.len()),
+        None => add_record(builder, record, per_seq_writer),
+    }
+}
+
+fn add_record(
+    builder: &mut MetricsBuilder,
+    record: &Record,
+    per_seq_writer: &mut Option<Box<dyn Write>>,
+) {
+    let record_len = record.len();
+
+    let seq = record.seq();
+    if seq.is_empty() && record_len > 0 {
+        // Only the length of GFA segments without sequence is known
+        builder.add_length(record_len);
+        return;
+    }
+
+    let avg_quality = builder.add_record(&seq, record.qual());
+    builder.add_pacbio_tags(record.pacbio_tags(), record_len);
+    if let Some(nanopore) = builder.nanopore.as_mut() {
+        nanopore.add_record(&nanopore::parse_header(record.id()), record_len);
+    }
+    if let Some(illumina) = builder.illumina.as_mut() {
+        illumina.add_record(record.id());
+    }
+    write_per_seq(record, per_seq_writer, avg_quality, record_len);
 }
 
 fn write_per_seq(
-    record: SequenceRecord,
-    writer: &mut Option<BufWriter<std::fs::File>>,
+    record: &Record,
+    writer: &mut Option<Box<dyn Write>>,
     avg_quality: f64,
     record_len: usize,
 ) {
     if let Some(writer) = writer {
         let record_id = std::str::from_utf8(record.id()).unwrap();
 
-        let record_gc = record
-            .seq()
+        let seq = record.seq();
+        let record_gc = seq
             .iter()
-            .filter(|c| **c == b'G' || **c == b'C')
+            .filter(|c| matches!(c, b'G' | b'C' | b'g' | b'c'))
             .count()
             * 100;
+        let record_masked = seq.iter().filter(|c| c.is_ascii_lowercase()).count() * 100;
 
         writeln!(
             writer,
-            "{}\t{}\t{}\t{}",
+            "{}\t{}\t{}\t{}\t{}",
             record_id,
             record_len,
             &format!("{:.2}", record_gc as f64 / record_len as f64),
             &format!("{:.2}", avg_quality),
+            &format!("{:.2}", record_masked as f64 / record_len as f64),
         )
         .unwrap();
     }
 }
 
-fn get_reader(file_path: &Path) -> Box<dyn needletail::FastxReader> {
-    assert!(file_path.exists(), "File not found {file_path:?}");
-
-    let file =
-        std::fs::File::open(file_path).unwrap_or_else(|e| panic!("Failed to open file: {e}"));
-    let buf_reader = std::io::BufReader::new(file);
-
-    let reader = if file_path.extension().take().unwrap_or_else(|| panic!("File extension should not be empty! As an example, file should be named 'toto.fasta' and not 'toto'.")) == "gz" {
-        let gz = GzDecoder::new(buf_reader);
-        needletail::parse_fastx_reader(gz).unwrap()
-    } else {
-        needletail::parse_fastx_reader(buf_reader).unwrap()
-    };
-
-    reader
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::inputs::parse_input;
 
     fn setup_reads_metrics() -> Metrics {
-        let path = std::path::Path::new("test_inputs/reads.fastq.gz");
+        let paths = [PathBuf::from("test_inputs/reads.fastq.gz")];
 
         let mut per_seq_writer = None;
 
-        compute_stats(path, 0, 0, 33, &mut per_seq_writer, None)
+        compute_stats(&paths, &test_config(), &mut per_seq_writer, None)
+    }
+
+    fn test_config() -> Config {
+        Config {
+            qual_offset: 33,
+            ..Default::default()
+        }
     }
 
     #[test]
@@ -257,4 +561,339 @@ mod tests {
         let metrics = setup_reads_metrics();
         assert_eq!(metrics.mean_quality, 8);
     }
+
+    #[test]
+    fn test_reads_checksum() {
+        let paths = [PathBuf::from("test_inputs/reads.fastq.gz")];
+        let config = Config {
+            checksum: Some(ChecksumOptions::default()),
+            ..test_config()
+        };
+        let metrics = compute_stats(&paths, &config, &mut None, None);
+        assert_eq!(metrics.checksum_digest, "d591714e83fb8510c891288915036526");
+    }
+
+    #[test]
+    fn test_grouped_input() {
+        let input =
+            parse_input("sample=test_inputs/reads.fastq.gz,test_inputs/reads.fastq.gz").unwrap();
+        assert_eq!(input.name.as_deref(), Some("sample"));
+        assert_eq!(input.paths.len(), 2);
+
+        let metrics = compute_stats(&input.paths, &test_config(), &mut None, input.name);
+        assert_eq!(metrics.filename, "sample");
+        assert_eq!(metrics.number, 2000);
+        assert_eq!(metrics.cumul, 2 * 5957360);
+        assert_eq!(metrics.n50, 8383);
+    }
+
+    #[test]
+    fn test_alignment_inputs() {
+        for path in ["test_inputs/reads.bam", "test_inputs/reads.sam"] {
+            let metrics = compute_stats(&[PathBuf::from(path)], &test_config(), &mut None, None);
+            assert_eq!(metrics.filename, "reads");
+            assert_eq!(metrics.number, 100);
+            assert_eq!(metrics.cumul, 13900);
+            assert_eq!(metrics.number_gc, 6914);
+            assert_eq!(metrics.secondary, 2);
+            assert_eq!(metrics.supplementary, 1);
+        }
+    }
+
+    #[test]
+    fn test_alignment_qual_offset() {
+        for path in ["test_inputs/reads.bam", "test_inputs/reads.sam"] {
+            let paths = [PathBuf::from(path)];
+            let metrics = compute_stats(&paths, &test_config(), &mut None, None);
+            let config = Config {
+                qual_offset: 64,
+                ..test_config()
+            };
+            let offset_metrics = compute_stats(&paths, &config, &mut None, None);
+            assert!(metrics.mean_quality > 0);
+            assert_eq!(offset_metrics.mean_quality, metrics.mean_quality);
+        }
+    }
+
+    #[test]
+    fn test_pacbio_tags() {
+        let paths = [PathBuf::from("test_inputs/hifi.bam")];
+        let metrics = compute_stats(&paths, &test_config(), &mut None, None);
+        assert_eq!(metrics.rq_reads, 50);
+        assert_eq!(metrics.rq_bins, [5, 10, 15, 10, 10]);
+        assert_eq!(metrics.hifi_reads, 35);
+        assert_eq!(metrics.hifi_yield, 35 * 1000);
+        assert_eq!(metrics.mean_passes, 7.42);
+    }
+
+    #[test]
+    fn test_nanopore_grouped_stats() {
+        let paths = [PathBuf::from("test_inputs/nanopore.fastq.gz")];
+        let config = Config {
+            nanopore: true,
+            ..test_config()
+        };
+        let metrics_vec = build_grouped_stats(&paths, &config, GroupBy::Barcode, &mut None, None)
+            .into_iter()
+            .map(MetricsBuilder::compute)
+            .collect::<Vec<Metrics>>();
+        let names = metrics_vec
+            .iter()
+            .map(|m| m.filename.as_str())
+            .collect::<Vec<&str>>();
+        assert_eq!(
+            names,
+            [
+                "nanopore barcode01",
+                "nanopore barcode02",
+                "nanopore unclassified"
+            ]
+        );
+        assert_eq!(metrics_vec[0].number, 38);
+
+        let nanopore = metrics_vec[0].nanopore.as_ref().unwrap();
+        assert_eq!(nanopore.run_ids.len(), 2);
+        assert_eq!(nanopore.channels.len(), 5);
+        assert_eq!(
+            nanopore.hourly.values().copied().collect::<Vec<_>>(),
+            [(13, 7770), (12, 9600), (13, 13030)]
+        );
+        let summary = metrics_vec[0].nanopore_summary.as_ref().unwrap();
+        assert_eq!(summary.flow_cells, "FAX00001");
+        assert_eq!(summary.reads_per_channel, [6, 8, 8]);
+    }
+
+    #[test]
+    fn test_illumina_grouped_stats() {
+        let paths = [PathBuf::from("test_inputs/pairs_R1.fastq.gz")];
+        let config = Config {
+            illumina: true,
+            ..test_config()
+        };
+        let metrics_vec = build_grouped_stats(&paths, &config, GroupBy::Lane, &mut None, None)
+            .into_iter()
+            .map(MetricsBuilder::compute)
+            .collect::<Vec<Metrics>>();
+        assert_eq!(metrics_vec.len(), 2);
+        assert_eq!(metrics_vec[1].filename, "pairs_R1 lane 2");
+        assert_eq!(metrics_vec[1].number, 50);
+
+        let illumina = metrics_vec[0].illumina.as_ref().unwrap();
+        assert_eq!(
+            illumina.top_indexes(2),
+            [("ACGTACGT+TTGGCCAA", 25), ("GGATCCAA+CCTTAAGG", 25)]
+        );
+    }
+
+    #[test]
+    fn test_grouped_reader_counts() {
+        let paths = [PathBuf::from("test_inputs/reads.bam")];
+        let config = Config {
+            sample: Some(SampleMode::Head(50)),
+            ..test_config()
+        };
+        let metrics_vec = build_grouped_stats(&paths, &config, GroupBy::Barcode, &mut None, None)
+            .into_iter()
+            .map(MetricsBuilder::compute)
+            .collect::<Vec<Metrics>>();
+        assert_eq!(metrics_vec.len(), 1);
+        assert_eq!(metrics_vec[0].number, 50);
+        assert!(metrics_vec[0].secondary > 0);
+        assert!(metrics_vec[0].sampling.is_some());
+    }
+
+    #[test]
+    fn test_gfa_input() {
+        let paths = [PathBuf::from("test_inputs/graph.gfa")];
+        let metrics = compute_stats(&paths, &test_config(), &mut None, None);
+        assert_eq!(metrics.filename, "graph");
+        assert_eq!(metrics.number, 6);
+        assert_eq!(metrics.cumul, 5100);
+        assert_eq!(metrics.number_gc, 200);
+        assert_eq!(metrics.links, 4);
+        assert_eq!(metrics.dead_ends, 5);
+        assert_eq!(metrics.components, 2);
+    }
+
+    #[test]
+    fn test_twobit_input() {
+        let paths = [PathBuf::from("test_inputs/genome.2bit")];
+        let metrics = compute_stats(&paths, &test_config(), &mut None, None);
+        assert_eq!(metrics.filename, "genome");
+        assert_eq!(metrics.number, 2);
+        assert_eq!(metrics.cumul, 57);
+        assert_eq!(metrics.number_n, 14);
+        assert_eq!(metrics.number_gc, 24);
+        assert_eq!(metrics.number_masked, 12);
+    }
+
+    #[test]
+    fn test_per_seq_output() {
+        let path =
+            std::env::temp_dir().join(format!("fastoche_per_seq_{}.tsv", std::process::id()));
+        let file = std::fs::File::create(&path).unwrap();
+        let mut per_seq_writer: Option<Box<dyn Write>> = Some(Box::new(file));
+        let paths = [PathBuf::from("test_inputs/genome.2bit")];
+        compute_stats(&paths, &test_config(), &mut per_seq_writer, None);
+        drop(per_seq_writer);
+
+        let per_seq = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(
+            per_seq,
+            "chr1\t36\t44.44\t0.00\t22.22\nchr2\t21\t38.10\t0.00\t19.05\n"
+        );
+    }
+
+    #[test]
+    fn test_protein_detection() {
+        let paths = [PathBuf::from("test_inputs/proteins.faa")];
+        let metrics = compute_stats(&paths, &test_config(), &mut None, None);
+        assert_eq!(metrics.filename, "proteins");
+        assert!(metrics.protein);
+        assert_eq!(metrics.number, 4);
+        assert_eq!(metrics.starts_with_m, 3);
+        assert_eq!(metrics.ends_with_stop, 3);
+        assert_eq!(metrics.internal_stops, 1);
+
+        let paths = [PathBuf::from("test_inputs/reads.fastq.gz")];
+        assert!(!compute_stats(&paths, &test_config(), &mut None, None).protein);
+    }
+
+    #[test]
+    fn test_filtered_stats() {
+        let paths = [PathBuf::from("test_inputs/reads.fastq.gz")];
+        let config = Config {
+            min_size: 1000,
+            max_size: Some(20000),
+            min_qual: Some(8.0),
+            ..test_config()
+        };
+        let mut output = Vec::new();
+        let (before, after) = build_filtered_stats(&paths, &config, &mut output, &mut None, None);
+        assert_eq!(before.filename, "reads (before filters)");
+        assert_eq!(before.seq_sizes.len(), 1000);
+        assert_eq!(after.filename, "reads (after filters)");
+        assert_eq!(after.seq_sizes.len(), 699);
+        assert!(after
+            .seq_sizes
+            .iter()
+            .all(|len| (1000..=20000).contains(len)));
+        assert!(after.quality_sum / after.quality_count as f64 >= 8.0);
+
+        // Each written record takes 4 Fastq lines
+        assert_eq!(output.iter().filter(|c| **c == b'\n').count(), 4 * 699);
+
+        // Reads and bases removed by --min-size, --max-size and --min-qual
+        assert_eq!(
+            after.removed[..3],
+            [(30, 18266), (17, 394337), (254, 1265495)]
+        );
+    }
+
+    #[test]
+    fn test_sampled_stats() {
+        let paths = [PathBuf::from("test_inputs/reads.fastq.gz")];
+        let config = Config {
+            sample: Some(SampleMode::Reads(100)),
+            seed: 42,
+            ..test_config()
+        };
+        let metrics = compute_stats(&paths, &config, &mut None, None);
+        assert_eq!(metrics.number, 100);
+        assert!(metrics.cumul < 5957360);
+
+        let sampling = metrics.sampling.unwrap();
+        assert_eq!(sampling.description, "100 reads (seed 42)");
+        assert_eq!(sampling.total_reads, 1000);
+        assert_eq!(sampling.total_bases, 5957360);
+        assert!(!sampling.extrapolated);
+    }
+
+    #[test]
+    fn test_head_stats() {
+        let paths = [PathBuf::from("test_inputs/reads.fastq.gz")];
+        let config = Config {
+            sample: Some(SampleMode::Head(300)),
+            ..test_config()
+        };
+        let metrics = compute_stats(&paths, &config, &mut None, None);
+        assert_eq!(metrics.number, 300);
+
+        // The estimates are close to the 1000 reads and 5957360 bases
+        let sampling = metrics.sampling.unwrap();
+        assert!(sampling.extrapolated);
+        assert!((950..1050).contains(&sampling.total_reads));
+        assert!((5_650_000..6_250_000).contains(&sampling.total_bases));
+
+        let config = Config {
+            sample: Some(SampleMode::HeadBytes(1_000_000)),
+            ..test_config()
+        };
+        let sampling = compute_stats(&paths, &config, &mut None, None)
+            .sampling
+            .unwrap();
+        assert!((950..1050).contains(&sampling.total_reads));
+
+        // Stopping after the last record gives exact totals
+        let config = Config {
+            sample: Some(SampleMode::Head(1000)),
+            ..test_config()
+        };
+        let sampling = compute_stats(&paths, &config, &mut None, None)
+            .sampling
+            .unwrap();
+        assert!(!sampling.extrapolated);
+        assert_eq!(sampling.total_reads, 1000);
+    }
+
+    #[test]
+    fn test_gfa_head() {
+        // The link is read before segment b, which is never read
+        let path = std::env::temp_dir().join(format!("fastoche_head_{}.gfa", std::process::id()));
+        std::fs::write(&path, "S\ta\tACGT\nL\ta\t+\tb\t+\t0M\nS\tc\tAC\nS\tb\tGG\n").unwrap();
+        let config = Config {
+            sample: Some(SampleMode::Head(1)),
+            ..test_config()
+        };
+        let metrics = compute_stats(std::slice::from_ref(&path), &config, &mut None, None);
+
+        // Once fully read, the graph metrics are computed
+        let whole = compute_stats(std::slice::from_ref(&path), &test_config(), &mut None, None);
+        assert_eq!(whole.links, 1);
+        assert_eq!(whole.components, 2);
+
+        // A link to a segment missing from the file only leaves them out
+        std::fs::write(&path, "S\ta\tACGT\nL\ta\t+\tb\t+\t0M\n").unwrap();
+        let invalid = compute_stats(std::slice::from_ref(&path), &test_config(), &mut None, None);
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(invalid.number, 1);
+        assert_eq!(invalid.components, 0);
+
+        assert_eq!(metrics.number, 1);
+        assert_eq!(metrics.links, 0);
+        assert_eq!(metrics.components, 0);
+        assert!(metrics
+            .sampling
+            .unwrap()
+            .description
+            .ends_with("graph metrics not computed"));
+    }
+
+    #[test]
+    fn test_gfa_percentages() {
+        // Segment c is only given by its length, and holds none of the bases
+        let paths = [PathBuf::from("test_inputs/graph.gfa")];
+        let metrics = compute_stats(&paths, &test_config(), &mut None, None);
+        assert_eq!(metrics.sequenced_bases, 3600);
+        assert_eq!(format!("{:.2}", metrics.percent_gc), "5.56");
+
+        let metrics_vec =
+            build_grouped_stats(&paths, &test_config(), GroupBy::Lane, &mut None, None)
+                .into_iter()
+                .map(MetricsBuilder::compute)
+                .collect::<Vec<Metrics>>();
+        assert_eq!(metrics_vec.len(), 1);
+        assert_eq!(metrics_vec[0].components, 2);
+    }
 }
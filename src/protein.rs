@@ -0,0 +1,63 @@
+/// Residues found in proteins but never in nucleotide sequences, even as
+/// IUPAC ambiguity codes
+const PROTEIN_ONLY_RESIDUES: &[u8] = b"EFILPQ";
+
+/// Minimum share of protein-only residues for a file to be detected as proteins.
+/// They make about a third of the residues of real proteomes.
+const PROTEIN_DETECTION_THRESHOLD: f64 = 0.1;
+
+/// Whether the character counts of a file look like those of protein sequences
+pub fn looks_like_protein(counts: &[usize; 256]) -> bool {
+    let letters = (b'A'..=b'Z')
+        .map(|c| counts[c as usize] + counts[c.to_ascii_lowercase() as usize])
+        .sum::<usize>();
+    let protein_only = PROTEIN_ONLY_RESIDUES
+        .iter()
+        .map(|c| counts[*c as usize] + counts[c.to_ascii_lowercase() as usize])
+        .sum::<usize>();
+
+    letters > 0 && protein_only as f64 / letters as f64 >= PROTEIN_DETECTION_THRESHOLD
+}
+
+/// Counts of the residues (letters and stops) found, case-insensitive, in
+/// alphabetical order
+pub fn residue_counts(counts: &[usize; 256]) -> Vec<(u8, usize)> {
+    (b'A'..=b'Z')
+        .map(|c| {
+            (
+                c,
+                counts[c as usize] + counts[c.to_ascii_lowercase() as usize],
+            )
+        })
+        .chain(std::iter::once((b'*', counts[b'*' as usize])))
+        .filter(|(_, count)| *count > 0)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn counts(seq: &[u8]) -> [usize; 256] {
+        let mut counts = [0; 256];
+        for c in seq {
+            counts[*c as usize] += 1;
+        }
+        counts
+    }
+
+    #[test]
+    fn test_looks_like_protein() {
+        assert!(looks_like_protein(&counts(b"MKVLLAGEPRSTQFI*")));
+        assert!(!looks_like_protein(&counts(b"ACGTNacgtnRYKM")));
+        assert!(!looks_like_protein(&counts(b"")));
+    }
+
+    #[test]
+    fn test_residue_counts() {
+        assert_eq!(
+            residue_counts(&counts(b"MKkM*")),
+            vec![(b'K', 2), (b'M', 2), (b'*', 1)]
+        );
+    }
+}
@@ -0,0 +1,101 @@
+//! Python module, built with maturin (`maturin develop --release`)
+
+use crate::catch_quietly;
+use crate::histogram::length_histogram;
+use crate::parser::{build_stats, Config};
+use crate::report::FIELDS;
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use pyo3::IntoPyObjectExt;
+use std::path::PathBuf;
+
+/// Computes the metrics of a Fastx, SAM/BAM, GFA or 2bit file. The returned
+/// dict holds the main fields of the CSV output (`FIELDS`), and with
+/// `histogram_bins` the length histogram as numpy-style `edges` and `counts`.
+#[pyfunction]
+#[pyo3(signature = (path, min_size=0, genome_size=0, quality_offset=33, histogram_bins=None))]
+fn stats<'py>(
+    py: Python<'py>,
+    path: PathBuf,
+    min_size: usize,
+    genome_size: i64,
+    quality_offset: u8,
+    histogram_bins: Option<usize>,
+) -> PyResult<Bound<'py, PyDict>> {
+    let config = Config {
+        min_size,
+        genome_size,
+        qual_offset: quality_offset,
+        ..Default::default()
+    };
+
+    // The core panics on invalid inputs, which is raised as a ValueError
+    let (metrics, histogram) = py
+        .detach(|| {
+            catch_quietly(|| {
+                let builder = build_stats(&[path], &config, &mut None, None);
+                let histogram =
+                    histogram_bins.map(|bins| length_histogram(&builder.seq_sizes, bins));
+                (builder.compute(), histogram)
+            })
+        })
+        .map_err(PyValueError::new_err)?;
+
+    let dict = PyDict::new(py);
+    dict.set_item("filename", &metrics.filename)?;
+    // Composition fields are None when only the lengths are known (NA in the
+    // CSV output)
+    let composition = metrics.has_composition && !metrics.protein;
+    for field in FIELDS {
+        let value = match field {
+            "number_n" | "percent_n" | "number_gc" | "percent_gc" | "number_masked"
+            | "percent_masked"
+                if !composition =>
+            {
+                py.None()
+            }
+            "percent_n" => metrics.percent_n.into_py_any(py)?,
+            "percent_gc" => metrics.percent_gc.into_py_any(py)?,
+            "percent_masked" => metrics.percent_masked.into_py_any(py)?,
+            "cumul" => metrics.cumul.into_py_any(py)?,
+            "number" => metrics.number.into_py_any(py)?,
+            "min_size" => metrics.min_size.into_py_any(py)?,
+            "max_size" => metrics.max_size.into_py_any(py)?,
+            "avg_size" => metrics.avg_size.into_py_any(py)?,
+            "aun" => metrics.aun.into_py_any(py)?,
+            "number_n" => metrics.number_n.into_py_any(py)?,
+            "number_gc" => metrics.number_gc.into_py_any(py)?,
+            "number_masked" => metrics.number_masked.into_py_any(py)?,
+            "n50" => metrics.n50.into_py_any(py)?,
+            "l50" => metrics.l50.into_py_any(py)?,
+            "n80" => metrics.n80.into_py_any(py)?,
+            "l80" => metrics.l80.into_py_any(py)?,
+            "n90" => metrics.n90.into_py_any(py)?,
+            "l90" => metrics.l90.into_py_any(py)?,
+            "ng50" => metrics.ng50.into_py_any(py)?,
+            "lg50" => metrics.lg50.into_py_any(py)?,
+            "ng80" => metrics.ng80.into_py_any(py)?,
+            "lg80" => metrics.lg80.into_py_any(py)?,
+            "ng90" => metrics.ng90.into_py_any(py)?,
+            "lg90" => metrics.lg90.into_py_any(py)?,
+            "mean_quality" => metrics.mean_quality.into_py_any(py)?,
+            _ => unreachable!("{field} is missing from the Python output"),
+        };
+        dict.set_item(field, value)?;
+    }
+
+    if let Some((edges, counts)) = histogram {
+        let histogram = PyDict::new(py);
+        histogram.set_item("edges", edges)?;
+        histogram.set_item("counts", counts)?;
+        dict.set_item("length_histogram", histogram)?;
+    }
+
+    Ok(dict)
+}
+
+#[pymodule]
+fn fastoche(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_function(wrap_pyfunction!(stats, m)?)
+}
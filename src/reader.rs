@@ -0,0 +1,258 @@
+use crate::alignment::{AlignmentReader, AlignmentRecord};
+use crate::gfa::{GfaReader, GraphStats, Segment};
+use crate::sample::SampledRecord;
+use crate::twobit::{TwoBitReader, TwoBitRecord};
+use flate2::bufread::MultiGzDecoder;
+use needletail::parser::SequenceRecord;
+use std::borrow::Cow;
+use std::io::{self, BufRead, BufReader, Read};
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+/// Sequence record of any of the supported input formats
+pub enum Record<'a> {
+    Fastx(SequenceRecord<'a>),
+    Alignment(&'a AlignmentRecord),
+    Gfa(&'a Segment),
+    TwoBit(&'a TwoBitRecord),
+    Sampled(&'a SampledRecord),
+}
+
+impl Record<'_> {
+    pub fn id(&self) -> &[u8] {
+        match self {
+            Record::Fastx(record) => record.id(),
+            Record::Alignment(record) => &record.id,
+            Record::Gfa(segment) => &segment.name,
+            Record::TwoBit(record) => &record.name,
+            Record::Sampled(record) => &record.id,
+        }
+    }
+
+    pub fn seq(&self) -> Cow<'_, [u8]> {
+        match self {
+            Record::Fastx(record) => record.seq(),
+            Record::Alignment(record) => Cow::Borrowed(&record.seq),
+            Record::Gfa(segment) => Cow::Borrowed(&segment.seq),
+            Record::TwoBit(record) => Cow::Borrowed(&record.seq),
+            Record::Sampled(record) => Cow::Borrowed(&record.seq),
+        }
+    }
+
+    /// Length of the sequence, which is only known from the LN tag of GFA
+    /// segments without sequence
+    pub fn len(&self) -> usize {
+        match self {
+            Record::Fastx(record) => record.num_bases(),
+            Record::Alignment(record) => record.seq.len(),
+            Record::Gfa(segment) => segment.len,
+            Record::TwoBit(record) => record.seq.len(),
+            Record::Sampled(record) => record.len,
+        }
+    }
+
+    /// Bytes of the record in the decompressed input, with the headers and
+    /// skipped records before it. Not known for 2bit files.
+    pub fn input_bytes(&self) -> u64 {
+        match self {
+            Record::Fastx(record) => record.all().len() as u64 + 1,
+            Record::Alignment(record) => record.input_bytes,
+            Record::Gfa(segment) => segment.input_bytes,
+            Record::TwoBit(_) | Record::Sampled(_) => 0,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn qual(&self) -> Option<&[u8]> {
+        match self {
+            Record::Fastx(record) => record.qual(),
+            Record::Alignment(record) => record.qual.as_deref(),
+            Record::Sampled(record) => record.qual.as_deref(),
+            Record::Gfa(_) | Record::TwoBit(_) => None,
+        }
+    }
+
+    /// PacBio predicted accuracy and number of passes, when tagged
+    pub fn pacbio_tags(&self) -> (Option<f32>, Option<u32>) {
+        match self {
+            Record::Alignment(record) => (record.rq, record.np),
+            Record::Sampled(record) => record.pacbio_tags,
+            _ => (None, None),
+        }
+    }
+}
+
+pub enum Reader {
+    Fastx(Box<dyn needletail::FastxReader>),
+    Alignment(AlignmentReader),
+    Gfa(GfaReader),
+    TwoBit(TwoBitReader),
+}
+
+impl Reader {
+    /// Next record of the file. Not an Iterator, as records borrow the
+    /// buffer of the reader, like those of needletail's FastxReader.
+    pub fn next_record(&mut self) -> Option<Record<'_>> {
+        match self {
+            Reader::Fastx(reader) => reader
+                .next()
+                .map(|record| Record::Fastx(record.expect("Error"))),
+            Reader::Alignment(reader) => reader.next_record().map(Record::Alignment),
+            Reader::Gfa(reader) => reader.next_record().map(Record::Gfa),
+            Reader::TwoBit(reader) => reader.next_record().map(Record::TwoBit),
+        }
+    }
+
+    /// Sets the offset of the qualities of SAM and BAM records, which are
+    /// converted from raw (BAM) or Phred+33 (SAM) scores. Other formats
+    /// keep the qualities of the file.
+    pub fn set_qual_offset(&mut self, qual_offset: u8) {
+        if let Reader::Alignment(reader) = self {
+            reader.qual_offset = qual_offset;
+        }
+    }
+
+    /// Number of secondary and supplementary records skipped so far
+    pub fn skipped_records(&self) -> (usize, usize) {
+        match self {
+            Reader::Alignment(reader) => (reader.secondary, reader.supplementary),
+            _ => (0, 0),
+        }
+    }
+
+    /// Links, dead ends and connected components of GFA files, once read.
+    /// Invalid graphs print a warning and have no graph metrics.
+    pub fn graph_stats(&self, path: &Path) -> Option<GraphStats> {
+        match self {
+            Reader::Gfa(reader) => reader
+                .graph_stats()
+                .inspect_err(|e| eprintln!("Warning: {e} in {path:?}, graph metrics left out"))
+                .ok(),
+            _ => None,
+        }
+    }
+}
+
+pub fn get_reader(file_path: &Path) -> Reader {
+    get_counting_reader(file_path).0
+}
+
+/// Bytes read from a file and from its decompressed stream, to find the
+/// share of the file holding the records read so far
+#[derive(Debug, Default)]
+pub struct Progress {
+    // Bytes of the file consumed by the decompression
+    file: Arc<AtomicU64>,
+    // Bytes of the decompressed stream read by the parser, which buffers
+    // them ahead of its records
+    stream: Arc<AtomicU64>,
+}
+
+impl Progress {
+    /// Bytes of the file holding the first `stream_bytes` of the decompressed
+    /// stream, assuming an even compression ratio. Always 0 for 2bit files.
+    pub fn file_bytes(&self, stream_bytes: u64) -> u64 {
+        let file = self.file.load(Ordering::Relaxed);
+        let stream = self.stream.load(Ordering::Relaxed);
+        if stream == 0 {
+            return 0;
+        }
+        (stream_bytes as f64 * file as f64 / stream as f64).round() as u64
+    }
+}
+
+/// Same as `get_reader`, with the progress of the reading. 2bit files are
+/// not counted, as they are read by seeking.
+pub fn get_counting_reader(file_path: &Path) -> (Reader, Progress) {
+    assert!(file_path.exists(), "File not found {file_path:?}");
+
+    let file =
+        std::fs::File::open(file_path).unwrap_or_else(|e| panic!("Failed to open file: {e}"));
+    let mut progress = Progress::default();
+
+    let mut extension = file_path.extension().unwrap_or_else(|| panic!("File extension should not be empty! As an example, file should be named 'toto.fasta' and not 'toto'."));
+    let gzipped = extension == "gz";
+    if gzipped {
+        extension = file_path
+            .file_stem()
+            .map(Path::new)
+            .and_then(|stem| stem.extension())
+            .unwrap_or_default();
+    }
+
+    // 2bit files need random access, so they are not checked for a .gz
+    // extension either
+    if extension == "2bit" {
+        assert!(!gzipped, "Compressed 2bit files are not supported");
+        let reader = Reader::TwoBit(TwoBitReader::new(BufReader::new(file)));
+        return (reader, progress);
+    } else if extension == "cram" {
+        panic!("CRAM files are not supported, convert them to BAM first (samtools view -b)")
+    }
+    assert!(
+        !(gzipped && extension == "bam"),
+        "BAM files are already compressed, {file_path:?} should not be gzipped"
+    );
+
+    // BAM files are always compressed, so they are not checked for a .gz
+    // extension
+    let stream: Box<dyn Read + Send> = if gzipped || extension == "bam" {
+        let file = CountingReader {
+            inner: BufReader::new(file),
+            count: Arc::clone(&progress.file),
+        };
+        Box::new(CountingReader {
+            inner: MultiGzDecoder::new(file),
+            count: Arc::clone(&progress.stream),
+        })
+    } else {
+        // The stream is the file itself
+        progress.stream = Arc::clone(&progress.file);
+        Box::new(CountingReader {
+            inner: file,
+            count: Arc::clone(&progress.file),
+        })
+    };
+
+    let reader = if extension == "bam" {
+        Reader::Alignment(AlignmentReader::bam(stream))
+    } else if extension == "sam" {
+        Reader::Alignment(AlignmentReader::sam(stream))
+    } else if extension == "gfa" {
+        Reader::Gfa(GfaReader::new(
+            Box::new(BufReader::new(stream)) as Box<dyn BufRead>
+        ))
+    } else {
+        Reader::Fastx(needletail::parse_fastx_reader(stream).unwrap())
+    };
+    (reader, progress)
+}
+
+/// Counts the bytes read through it, or consumed from its buffer
+struct CountingReader<R> {
+    inner: R,
+    count: Arc<AtomicU64>,
+}
+
+impl<R: Read> Read for CountingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.count.fetch_add(n as u64, Ordering::Relaxed);
+        Ok(n)
+    }
+}
+
+impl<R: BufRead> BufRead for CountingReader<R> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        self.inner.fill_buf()
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.count.fetch_add(amt as u64, Ordering::Relaxed);
+        self.inner.consume(amt);
+    }
+}
@@ -1,9 +1,18 @@
+use crate::composition::{char_name, Composition};
+use crate::filter::Filter;
 use crate::formatted_metrics::FormattedMetrics;
 use crate::metrics::Metrics;
-use tabled::object::{Columns, LastRow, Object, Rows};
-use tabled::{Alignment, Disable, Modify, Style, Table};
+use crate::nanopore::yield_per_hour;
+use crate::parser::Config;
+use crate::protein::residue_counts;
+use std::collections::BTreeSet;
+use std::io::{self, Write};
+use tabled::builder::Builder;
+use tabled::object::{Columns, Object, Rows};
+use tabled::{Alignment, Disable, Modify, Style, Table, Tabled};
+use thousands::Separable;
 
-const FIELDS: [&str; 23] = [
+pub const FIELDS: [&str; 25] = [
     "cumul",
     "number",
     "min_size",
@@ -27,11 +36,62 @@ const FIELDS: [&str; 23] = [
     "ng90",
     "lg90",
     "mean_quality",
+    // Appended so that the columns of existing scripts keep their position
+    "number_masked",
+    "percent_masked",
 ];
 
-// const OPTIONAL_FIELDS: [&str; 4] = ["ng50", "ng80", "ng90", "mean_quality"];
+// Number of index sequences listed for each input with --illumina
+const TOP_INDEXES: usize = 20;
+
+// Width of the largest bar of length histograms
+const HISTOGRAM_WIDTH: usize = 50;
+
+// Fields only output when at least one file computed them
+const OPTIONAL_FIELDS: [&str; 34] = [
+    "checksum",
+    "pairs",
+    "short_pairs",
+    "percent_short_pairs",
+    "secondary",
+    "supplementary",
+    "rq_below_q10",
+    "rq_q10_q19",
+    "rq_q20_q29",
+    "rq_q30_q39",
+    "rq_q40_above",
+    "hifi_reads",
+    "percent_hifi_reads",
+    "hifi_yield",
+    "percent_hifi_yield",
+    "mean_passes",
+    "group",
+    "run_ids",
+    "flow_cells",
+    "active_channels",
+    "min_reads_per_channel",
+    "median_reads_per_channel",
+    "max_reads_per_channel",
+    "links",
+    "dead_ends",
+    "components",
+    "starts_with_m",
+    "percent_starts_with_m",
+    "ends_with_stop",
+    "percent_ends_with_stop",
+    "internal_stops",
+    "total_reads",
+    "total_bases",
+    "estimated_totals",
+];
+
+/// Writes the metrics of the inputs as a table, one column per input. Nothing
+/// is written without inputs.
+pub fn write_table<W: Write>(w: &mut W, metrics_vec: &[Metrics]) -> io::Result<()> {
+    if metrics_vec.is_empty() {
+        return Ok(());
+    }
 
-pub fn print(metrics_vec: &[Metrics]) {
     let fmt = metrics_vec
         .iter()
         .map(FormattedMetrics::from_metrics)
@@ -51,59 +111,492 @@ pub fn print(metrics_vec: &[Metrics]) {
         .with(Disable::row(Rows::first()));
 
     // Only display NGX and quality if their are greater that 0
+    let mut hidden_rows = Vec::new();
     if fmt[0].ng50_lg50 == "0 (0)" {
-        styled_table = styled_table.with(Disable::row(Rows::new(12..15)));
+        hidden_rows.extend(["NG50 (LG50)", "NG80 (LG80)", "NG90 (LG90)"]);
+    }
+    if metrics_vec.iter().all(|m| m.number_masked == 0) {
+        hidden_rows.push("Soft-masked");
     }
     if fmt[0].mean_quality == "0" {
-        styled_table = styled_table.with(Disable::row(LastRow));
+        hidden_rows.push("Mean quality");
+    }
+    if fmt.iter().all(|m| m.checksum.is_empty()) {
+        hidden_rows.push("Checksum");
+    }
+    if metrics_vec.iter().all(|m| m.pairs == 0) {
+        hidden_rows.extend(["Pairs", "Short mate pairs"]);
+    }
+    if metrics_vec
+        .iter()
+        .all(|m| m.secondary == 0 && m.supplementary == 0)
+    {
+        hidden_rows.extend(["Skipped secondary", "Skipped supplementary"]);
+    }
+    if metrics_vec.iter().all(|m| m.rq_reads == 0) {
+        hidden_rows.extend([
+            "rq < Q10",
+            "rq Q10-Q19",
+            "rq Q20-Q29",
+            "rq Q30-Q39",
+            "rq >= Q40",
+            "HiFi reads (>= Q20)",
+            "HiFi yield",
+        ]);
+    }
+    if metrics_vec.iter().all(|m| m.np_reads == 0) {
+        hidden_rows.push("Mean passes");
+    }
+    if metrics_vec.iter().all(|m| m.components == 0) {
+        hidden_rows.extend(["Links", "Dead ends", "Connected components"]);
+    }
+    if metrics_vec.iter().all(|m| !m.protein) {
+        hidden_rows.extend(["Starts with M", "Ends with *", "Internal stops"]);
+    }
+
+    // Rows are removed from the bottom up so that the indices stay valid
+    let headers = FormattedMetrics::headers();
+    let mut hidden_indices = hidden_rows
+        .iter()
+        .map(|row| headers.iter().position(|h| h == row).unwrap())
+        .collect::<Vec<usize>>();
+    hidden_indices.sort_unstable();
+    for i in hidden_indices.into_iter().rev() {
+        styled_table = styled_table.with(Disable::row(Rows::single(i)));
+    }
+
+    writeln!(w, "{styled_table}")?;
+
+    if metrics_vec.iter().any(|m| m.nanopore.is_some()) {
+        write_nanopore(w, metrics_vec)?;
+    }
+    if metrics_vec.iter().any(|m| m.illumina.is_some()) {
+        write_illumina_indexes(w, metrics_vec)?;
+    }
+    if metrics_vec
+        .iter()
+        .any(|m| m.removed.iter().any(|r| r.0 > 0))
+    {
+        write_removed(w, metrics_vec)?;
+    }
+    if metrics_vec.iter().any(|m| m.sampling.is_some()) {
+        write_sampling(w, metrics_vec)?;
+    }
+    Ok(())
+}
+
+/// Writes the part of each input the metrics were computed on, and the
+/// totals of the whole input
+fn write_sampling<W: Write>(w: &mut W, metrics_vec: &[Metrics]) -> io::Result<()> {
+    let mut builder = Builder::default();
+    builder.set_columns(
+        std::iter::once(String::new()).chain(metrics_vec.iter().map(|m| m.filename.clone())),
+    );
+
+    let mut description = vec!["Metrics computed on".to_string()];
+    let mut total_reads = vec!["Total reads (whole input)".to_string()];
+    let mut total_bases = vec!["Total bases (whole input)".to_string()];
+    for m in metrics_vec {
+        match &m.sampling {
+            Some(sampling) => {
+                // Extrapolated totals are marked as estimates
+                let prefix = if sampling.extrapolated { "~" } else { "" };
+                description.push(sampling.description.clone());
+                total_reads.push(format!(
+                    "{prefix}{}",
+                    sampling.total_reads.separate_with_commas()
+                ));
+                total_bases.push(format!(
+                    "{prefix}{}",
+                    sampling.total_bases.separate_with_commas()
+                ));
+            }
+            None => {
+                description.push("all the reads".to_string());
+                total_reads.push(m.number.separate_with_commas());
+                total_bases.push(m.cumul.separate_with_commas());
+            }
+        }
+    }
+    builder.add_record(description);
+    builder.add_record(total_reads);
+    builder.add_record(total_bases);
+
+    let mut table = builder.build();
+    table
+        .with(Style::sharp())
+        .with(Modify::new(Columns::first()).with(Alignment::left()))
+        .with(Modify::new(Columns::first().inverse()).with(Alignment::right()));
+
+    writeln!(w, "{table}")
+}
+
+/// Writes the reads and bases removed by each filter that removed any
+fn write_removed<W: Write>(w: &mut W, metrics_vec: &[Metrics]) -> io::Result<()> {
+    let mut builder = Builder::default();
+    builder.set_columns(
+        std::iter::once(String::new()).chain(metrics_vec.iter().map(|m| m.filename.clone())),
+    );
+
+    for (i, filter) in Filter::ALL.iter().enumerate() {
+        if metrics_vec.iter().all(|m| m.removed[i].0 == 0) {
+            continue;
+        }
+        let mut row = vec![format!("Removed by {}", filter.flag())];
+        for m in metrics_vec {
+            let (reads, bases) = m.removed[i];
+            row.push(format!(
+                "{} reads ({} bases)",
+                reads.separate_with_commas(),
+                bases.separate_with_commas()
+            ));
+        }
+        builder.add_record(row);
+    }
+
+    let mut table = builder.build();
+    table
+        .with(Style::sharp())
+        .with(Modify::new(Columns::first()).with(Alignment::left()))
+        .with(Modify::new(Columns::first().inverse()).with(Alignment::right()));
+
+    writeln!(w, "{table}")
+}
+
+/// Writes the report selected by the config: a table (with the optional
+/// composition tables), CSV or parsable output
+pub fn write_report<W: Write>(
+    w: &mut W,
+    metrics_vec: &[Metrics],
+    config: &Config,
+) -> io::Result<()> {
+    if config.csv {
+        write_csv(w, metrics_vec)
+    } else if config.parsable {
+        write_parsable(w, metrics_vec, &config.output_fields, config.no_header)
+    } else {
+        write_table(w, metrics_vec)?;
+        if config.composition {
+            write_composition(w, metrics_vec)?;
+        }
+        if metrics_vec.iter().any(|m| m.protein) {
+            write_amino_acids(w, metrics_vec)?;
+        }
+        Ok(())
+    }
+}
+
+/// Writes the count of each base, ambiguity code and unexpected character
+/// found in the inputs
+pub fn write_composition<W: Write>(w: &mut W, metrics_vec: &[Metrics]) -> io::Result<()> {
+    if metrics_vec.is_empty() {
+        return Ok(());
+    }
+
+    let compositions = metrics_vec
+        .iter()
+        .map(|m| Composition::from_counts(&m.nucleotide_counts))
+        .collect::<Vec<Composition>>();
+
+    let mut builder = Builder::default();
+    builder.set_columns(
+        std::iter::once(String::new()).chain(metrics_vec.iter().map(|m| m.filename.clone())),
+    );
+
+    // Bases, then the ambiguity codes and unexpected characters found in any input
+    let ambiguity = compositions
+        .iter()
+        .flat_map(|c| c.ambiguity.iter().map(|(c, _)| *c))
+        .collect::<BTreeSet<u8>>();
+    let unexpected = compositions
+        .iter()
+        .flat_map(|c| c.unexpected.iter().map(|(c, _)| *c))
+        .collect::<BTreeSet<u8>>();
+    let rows = compositions[0]
+        .bases
+        .iter()
+        .map(|(c, _)| (*c, char_name(*c)))
+        .chain(
+            ambiguity
+                .into_iter()
+                .map(|c| (c, format!("{} (ambiguity)", char_name(c)))),
+        )
+        .chain(
+            unexpected
+                .into_iter()
+                .map(|c| (c, format!("{} (unexpected)", char_name(c)))),
+        );
+
+    for (c, label) in rows {
+        let mut row = vec![label];
+        for (m, composition) in metrics_vec.iter().zip(&compositions) {
+            let count = [
+                &composition.bases,
+                &composition.ambiguity,
+                &composition.unexpected,
+            ]
+            .into_iter()
+            .flatten()
+            .find(|(other, _)| *other == c)
+            .map_or(0, |(_, count)| *count);
+            row.push(format!(
+                "{} ({:.2}%)",
+                count.separate_with_commas(),
+                (count as f64 / m.sequenced_bases as f64) * 100.0
+            ));
+        }
+        builder.add_record(row);
+    }
+
+    let mut table = builder.build();
+    table
+        .with(Style::sharp())
+        .with(Modify::new(Columns::first()).with(Alignment::left()))
+        .with(Modify::new(Columns::first().inverse()).with(Alignment::right()));
+
+    writeln!(w, "{table}")
+}
+
+/// Writes the amino-acid composition of the protein inputs
+pub fn write_amino_acids<W: Write>(w: &mut W, metrics_vec: &[Metrics]) -> io::Result<()> {
+    let proteins = metrics_vec
+        .iter()
+        .filter(|m| m.protein)
+        .map(|m| (m, residue_counts(&m.nucleotide_counts)))
+        .collect::<Vec<_>>();
+
+    let mut builder = Builder::default();
+    builder.set_columns(
+        std::iter::once(String::new()).chain(proteins.iter().map(|(m, _)| m.filename.clone())),
+    );
+
+    let residues = proteins
+        .iter()
+        .flat_map(|(_, counts)| counts.iter().map(|(c, _)| *c))
+        .collect::<BTreeSet<u8>>();
+    // Stops are listed after the amino acids
+    let residues = (b'A'..=b'Z')
+        .chain(std::iter::once(b'*'))
+        .filter(|c| residues.contains(c));
+    for residue in residues {
+        let mut row = vec![(residue as char).to_string()];
+        for (m, counts) in &proteins {
+            let count = counts
+                .iter()
+                .find(|(c, _)| *c == residue)
+                .map_or(0, |(_, count)| *count);
+            row.push(format!(
+                "{} ({:.2}%)",
+                count.separate_with_commas(),
+                (count as f64 / m.sequenced_bases as f64) * 100.0
+            ));
+        }
+        builder.add_record(row);
+    }
+
+    let mut table = builder.build();
+    table
+        .with(Style::sharp())
+        .with(Modify::new(Columns::first()).with(Alignment::left()))
+        .with(Modify::new(Columns::first().inverse()).with(Alignment::right()));
+
+    writeln!(w, "{table}")
+}
+
+/// Writes the most frequent index sequences of each input, which shows
+/// demultiplexing problems when run on undetermined reads
+fn write_illumina_indexes<W: Write>(w: &mut W, metrics_vec: &[Metrics]) -> io::Result<()> {
+    let mut builder = Builder::default();
+    builder.set_columns(["", "Index", "Reads", "% of reads"]);
+
+    for m in metrics_vec {
+        if let Some(illumina) = &m.illumina {
+            for (i, (index, count)) in illumina.top_indexes(TOP_INDEXES).into_iter().enumerate() {
+                let name = if i == 0 { m.filename.as_str() } else { "" };
+                builder.add_record([
+                    name.to_string(),
+                    index.to_string(),
+                    count.separate_with_commas(),
+                    format!("{:.2}", (count as f64 / illumina.reads as f64) * 100.0),
+                ]);
+            }
+        }
+    }
+
+    let mut table = builder.build();
+    table
+        .with(Style::sharp())
+        .with(Modify::new(Columns::first()).with(Alignment::left()))
+        .with(Modify::new(Columns::new(2..)).with(Alignment::right()));
+
+    writeln!(w, "{table}")
+}
+
+/// Writes the channel activity and the yield per hour of Nanopore runs
+fn write_nanopore<W: Write>(w: &mut W, metrics_vec: &[Metrics]) -> io::Result<()> {
+    let nanopore_vec = metrics_vec
+        .iter()
+        .filter_map(|m| m.nanopore.as_ref().map(|n| (m.filename.clone(), n)))
+        .collect::<Vec<_>>();
+
+    let mut builder = Builder::default();
+    builder.set_columns(
+        std::iter::once(String::new()).chain(nanopore_vec.iter().map(|(name, _)| name.clone())),
+    );
+
+    let mut run_ids = vec!["Run ids".to_string()];
+    let mut flow_cells = vec!["Flow cells".to_string()];
+    let mut channels = vec!["Active channels".to_string()];
+    let mut reads_per_channel = vec!["Reads per channel (min/med/max)".to_string()];
+    for (_, nanopore) in &nanopore_vec {
+        let summary = nanopore.summary();
+        let [min, median, max] = summary.reads_per_channel;
+        run_ids.push(summary.run_ids.to_string());
+        flow_cells.push(summary.flow_cells.replace(';', ","));
+        channels.push(summary.active_channels.separate_with_commas());
+        reads_per_channel.push(format!(
+            "{}/{}/{}",
+            min.separate_with_commas(),
+            median.separate_with_commas(),
+            max.separate_with_commas()
+        ));
+    }
+    builder.add_record(run_ids);
+    builder.add_record(flow_cells);
+    builder.add_record(channels);
+    builder.add_record(reads_per_channel);
+
+    // Yield of each hour since the first read of any input, as bases (reads)
+    let stats = nanopore_vec.iter().map(|(_, n)| *n).collect::<Vec<_>>();
+    for (hour, yields) in yield_per_hour(&stats) {
+        let mut row = vec![format!("Yield hour {hour}")];
+        for (reads, bases) in yields {
+            row.push(format!(
+                "{} ({})",
+                bases.separate_with_commas(),
+                reads.separate_with_commas()
+            ));
+        }
+        builder.add_record(row);
+    }
+
+    let mut table = builder.build();
+    table
+        .with(Style::sharp())
+        .with(Modify::new(Columns::first()).with(Alignment::left()))
+        .with(Modify::new(Columns::first().inverse()).with(Alignment::right()));
+
+    writeln!(w, "{table}")
+}
+
+/// Writes the length histogram of an input, with a bar for each bin
+pub fn write_histogram<W: Write>(
+    w: &mut W,
+    name: &str,
+    edges: &[f64],
+    counts: &[usize],
+) -> io::Result<()> {
+    let max_count = counts.iter().max().copied().unwrap_or(0).max(1);
+
+    let mut builder = Builder::default();
+    builder.set_columns([name, "Sequences", ""]);
+    for (bin, count) in edges.windows(2).zip(counts) {
+        builder.add_record([
+            format!("{:.0} - {:.0}", bin[0], bin[1]),
+            count.separate_with_commas(),
+            "#".repeat(count * HISTOGRAM_WIDTH / max_count),
+        ]);
+    }
+
+    let mut table = builder.build();
+    table
+        .with(Style::sharp())
+        .with(Modify::new(Columns::single(1)).with(Alignment::right()));
+
+    writeln!(w, "{table}")
+}
+
+/// Writes the metrics of two inputs side by side, with the difference of
+/// the second to the first
+pub fn write_comparison<W: Write>(w: &mut W, first: &Metrics, second: &Metrics) -> io::Result<()> {
+    let mut builder = Builder::default();
+    builder.set_columns(["", &first.filename, &second.filename, "Difference"]);
+
+    for field in FIELDS {
+        let (a, b) = (first[field].to_string(), second[field].to_string());
+        let difference = match (a.parse::<i128>(), b.parse::<i128>()) {
+            (Ok(a), Ok(b)) => format!("{:+}", b - a),
+            _ => match (a.parse::<f64>(), b.parse::<f64>()) {
+                (Ok(a), Ok(b)) => format!("{:+.2}", b - a),
+                _ => String::new(),
+            },
+        };
+        builder.add_record([field.to_string(), a, b, difference]);
+    }
+    if !first.checksum_digest.is_empty() && !second.checksum_digest.is_empty() {
+        let same = first.checksum_digest == second.checksum_digest;
+        builder.add_record([
+            "checksum".to_string(),
+            first.checksum_digest.clone(),
+            second.checksum_digest.clone(),
+            if same { "identical" } else { "different" }.to_string(),
+        ]);
     }
 
-    println!("{styled_table}");
+    let mut table = builder.build();
+    table
+        .with(Style::sharp())
+        .with(Modify::new(Columns::first()).with(Alignment::left()))
+        .with(Modify::new(Columns::first().inverse()).with(Alignment::right()));
+
+    writeln!(w, "{table}")
 }
 
-pub fn print_csv(metrics_vec: &[Metrics]) {
-    print!("filename");
+pub fn write_csv<W: Write>(w: &mut W, metrics_vec: &[Metrics]) -> io::Result<()> {
+    write!(w, "filename")?;
     for m in metrics_vec.iter() {
-        print!(",{}", m.filename);
+        write!(w, ",{}", m.filename)?;
     }
 
-    for f in FIELDS {
-        print!("\n{f}");
+    for f in default_fields(metrics_vec) {
+        write!(w, "\n{f}")?;
         for m in metrics_vec {
-            print!(",{}", &m[f]);
+            write!(w, ",{}", &m[f.as_str()])?;
         }
     }
 
-    println!();
+    writeln!(w)
 }
 
-pub fn print_parsable(
+pub fn write_parsable<W: Write>(
+    w: &mut W,
     metrics_vec: &[Metrics],
     user_output_fields: &Option<Vec<String>>,
     no_header: bool,
-) {
+) -> io::Result<()> {
     // choose the output fields
-    let default_output_fields = FIELDS.map(|x| x.to_owned()).to_vec();
+    let default_output_fields = default_fields(metrics_vec);
     let output_fields = match user_output_fields {
         Some(x) => x,
         None => &default_output_fields,
     };
     // maybe print a header
     if !no_header {
-        print!("filename");
+        write!(w, "filename")?;
         for f in output_fields {
-            print!(",{f}");
+            write!(w, ",{f}")?;
         }
-        println!();
+        writeln!(w)?;
     }
     // print the metrics for this file
     for m in metrics_vec {
-        print!("{}", m.filename);
+        write!(w, "{}", m.filename)?;
         for f in output_fields {
-            print!(",{}", &m[f]);
+            write!(w, ",{}", &m[f])?;
         }
-        println!();
+        writeln!(w)?;
     }
+    Ok(())
 }
 
 pub fn parse_output_format(output_format: &Option<String>) -> Option<Vec<String>> {
@@ -111,7 +604,10 @@ pub fn parse_output_format(output_format: &Option<String>) -> Option<Vec<String>
         Some(format_str) => {
             let mut output_fields = Vec::new();
             for field in format_str.split(',') {
-                if !FIELDS.contains(&field) {
+                if !FIELDS.contains(&field)
+                    && !OPTIONAL_FIELDS.contains(&field)
+                    && !removed_fields(Filter::ALL).contains(&field.to_owned())
+                {
                     panic!("{field} is not a valid field")
                 }
                 output_fields.push(field.to_owned());
@@ -124,3 +620,109 @@ pub fn parse_output_format(output_format: &Option<String>) -> Option<Vec<String>
         None => None,
     }
 }
+
+/// Removed reads and bases fields of the given filters
+fn removed_fields(filters: impl IntoIterator<Item = Filter>) -> Vec<String> {
+    filters
+        .into_iter()
+        .flat_map(|f| {
+            [
+                format!("removed_reads_{}", f.field()),
+                format!("removed_bases_{}", f.field()),
+            ]
+        })
+        .collect()
+}
+
+fn default_fields(metrics_vec: &[Metrics]) -> Vec<String> {
+    let mut fields = FIELDS.map(|x| x.to_owned()).to_vec();
+    if metrics_vec.iter().any(|m| !m.checksum_digest.is_empty()) {
+        fields.push("checksum".to_owned());
+    }
+    if metrics_vec.iter().any(|m| m.pairs > 0) {
+        fields.extend(["pairs", "short_pairs", "percent_short_pairs"].map(|x| x.to_owned()));
+    }
+    if metrics_vec
+        .iter()
+        .any(|m| m.secondary > 0 || m.supplementary > 0)
+    {
+        fields.extend(["secondary", "supplementary"].map(|x| x.to_owned()));
+    }
+    if metrics_vec.iter().any(|m| m.rq_reads > 0) {
+        fields.extend(
+            [
+                "rq_below_q10",
+                "rq_q10_q19",
+                "rq_q20_q29",
+                "rq_q30_q39",
+                "rq_q40_above",
+                "hifi_reads",
+                "percent_hifi_reads",
+                "hifi_yield",
+                "percent_hifi_yield",
+            ]
+            .map(|x| x.to_owned()),
+        );
+    }
+    if metrics_vec.iter().any(|m| m.np_reads > 0) {
+        fields.push("mean_passes".to_owned());
+    }
+    if metrics_vec.iter().any(|m| !m.group.is_empty()) {
+        fields.push("group".to_owned());
+    }
+    if metrics_vec.iter().any(|m| m.nanopore.is_some()) {
+        fields.extend(
+            [
+                "run_ids",
+                "flow_cells",
+                "active_channels",
+                "min_reads_per_channel",
+                "median_reads_per_channel",
+                "max_reads_per_channel",
+            ]
+            .map(|x| x.to_owned()),
+        );
+    }
+    if metrics_vec.iter().any(|m| m.components > 0) {
+        fields.extend(["links", "dead_ends", "components"].map(|x| x.to_owned()));
+    }
+    if metrics_vec.iter().any(|m| m.protein) {
+        fields.extend(
+            [
+                "starts_with_m",
+                "percent_starts_with_m",
+                "ends_with_stop",
+                "percent_ends_with_stop",
+                "internal_stops",
+            ]
+            .map(|x| x.to_owned()),
+        );
+    }
+    if metrics_vec.iter().any(|m| m.sampling.is_some()) {
+        fields.extend(["total_reads", "total_bases", "estimated_totals"].map(|x| x.to_owned()));
+    }
+    // --min-size is left out, as it was output without its removed fields
+    // before the other filters existed
+    fields.extend(removed_fields(
+        Filter::ALL
+            .into_iter()
+            .enumerate()
+            .filter(|(_, f)| *f != Filter::MinSize)
+            .filter(|(i, _)| metrics_vec.iter().any(|m| m.removed[*i].0 > 0))
+            .map(|(_, f)| f),
+    ));
+    fields
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_empty_reports() {
+        let mut output = Vec::new();
+        write_table(&mut output, &[]).unwrap();
+        write_composition(&mut output, &[]).unwrap();
+        assert!(output.is_empty());
+    }
+}
@@ -0,0 +1,243 @@
+use crate::reader::Record;
+use serde::{Deserialize, Serialize};
+use thousands::Separable;
+
+/// How the records of an input are subsampled
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SampleMode {
+    /// Each record is kept with this probability
+    Fraction(f64),
+    /// Uniform sample of this number of records (reservoir sampling)
+    Reads(usize),
+    /// First records of the input
+    Head(usize),
+    /// Records in about this number of bytes at the start of the input
+    HeadBytes(u64),
+}
+
+/// Part of an input the metrics were computed on, with the totals of the
+/// whole input. Totals are exact when every record was read, and
+/// extrapolated otherwise.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Sampling {
+    pub description: String,
+    pub total_reads: usize,
+    pub total_bases: usize,
+    pub extrapolated: bool,
+}
+
+impl Sampling {
+    /// Adds the totals of another chunk of the same input
+    pub fn merge(&mut self, other: &Sampling) {
+        self.total_reads += other.total_reads;
+        self.total_bases += other.total_bases;
+        self.extrapolated |= other.extrapolated;
+    }
+
+    /// Scales the totals of the records read up to the whole input, from the
+    /// share of its bytes that were read
+    pub fn extrapolate(&mut self, bytes_read: u64, total_bytes: u64) {
+        let ratio = (total_bytes as f64 / bytes_read as f64).max(1.0);
+        self.total_reads = (self.total_reads as f64 * ratio).round() as usize;
+        self.total_bases = (self.total_bases as f64 * ratio).round() as usize;
+        self.extrapolated = true;
+    }
+}
+
+/// SplitMix64 generator, which is enough for sampling and keeps samples
+/// reproducible across platforms and versions
+pub struct SplitMix64(u64);
+
+impl SplitMix64 {
+    pub fn new(seed: u64) -> Self {
+        SplitMix64(seed)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e3779b97f4a7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform float in [0, 1)
+    pub fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// Uniform integer in [0, n)
+    pub fn below(&mut self, n: usize) -> usize {
+        ((self.next_u64() as u128 * n as u128) >> 64) as usize
+    }
+}
+
+/// Owned copy of a record kept in a reservoir
+#[derive(Debug)]
+pub struct SampledRecord {
+    pub id: Vec<u8>,
+    pub seq: Vec<u8>,
+    pub qual: Option<Vec<u8>>,
+    pub len: usize,
+    pub pacbio_tags: (Option<f32>, Option<u32>),
+}
+
+impl SampledRecord {
+    fn new(record: &Record) -> Self {
+        SampledRecord {
+            id: record.id().to_vec(),
+            seq: record.seq().into_owned(),
+            qual: record.qual().map(|qual| qual.to_vec()),
+            len: record.len(),
+            pacbio_tags: record.pacbio_tags(),
+        }
+    }
+}
+
+/// Subsamples the records of an input, counting all of them
+pub struct Sampler {
+    mode: SampleMode,
+    seed: u64,
+    rng: SplitMix64,
+    reads: usize,
+    bases: usize,
+    reservoir: Vec<(usize, SampledRecord)>,
+}
+
+impl Sampler {
+    pub fn new(mode: SampleMode, seed: u64) -> Self {
+        Sampler {
+            mode,
+            seed,
+            rng: SplitMix64::new(seed),
+            reads: 0,
+            bases: 0,
+            reservoir: Vec::new(),
+        }
+    }
+
+    /// Whether a record is kept. In reservoir mode, records are never kept
+    /// right away but may be stored, and the sample is only known once every
+    /// record was seen.
+    pub fn sample(&mut self, record: &Record) -> bool {
+        let index = self.reads;
+        self.reads += 1;
+        self.bases += record.len();
+
+        match self.mode {
+            SampleMode::Fraction(fraction) => self.rng.next_f64() < fraction,
+            SampleMode::Head(_) | SampleMode::HeadBytes(_) => true,
+            SampleMode::Reads(reads) => {
+                if index < reads {
+                    self.reservoir.push((index, SampledRecord::new(record)));
+                } else {
+                    let i = self.rng.below(index + 1);
+                    if i < reads {
+                        self.reservoir[i] = (index, SampledRecord::new(record));
+                    }
+                }
+                false
+            }
+        }
+    }
+
+    /// Whether the reading can stop, given the bytes read from the input
+    pub fn is_done(&self, bytes_read: u64) -> bool {
+        match self.mode {
+            SampleMode::Head(reads) => self.reads >= reads,
+            SampleMode::HeadBytes(bytes) => bytes_read >= bytes,
+            _ => false,
+        }
+    }
+
+    pub fn sampling(&self) -> Sampling {
+        let description = match self.mode {
+            SampleMode::Fraction(fraction) => {
+                format!(
+                    "{}% of the reads (seed {})",
+                    format_percent(fraction * 100.0),
+                    self.seed
+                )
+            }
+            SampleMode::Reads(reads) => {
+                format!(
+                    "{} reads (seed {})",
+                    reads.separate_with_commas(),
+                    self.seed
+                )
+            }
+            SampleMode::Head(reads) => format!("first {} reads", reads.separate_with_commas()),
+            SampleMode::HeadBytes(bytes) => {
+                format!("first {} bytes", bytes.separate_with_commas())
+            }
+        };
+
+        Sampling {
+            description,
+            total_reads: self.reads,
+            total_bases: self.bases,
+            extrapolated: false,
+        }
+    }
+
+    /// Records of the reservoir, in the order of the input
+    pub fn into_reservoir(self) -> Vec<SampledRecord> {
+        let mut reservoir = self.reservoir;
+        reservoir.sort_unstable_by_key(|(index, _)| *index);
+        reservoir.into_iter().map(|(_, record)| record).collect()
+    }
+}
+
+/// Percentage with up to 4 decimals, without trailing zeros (7% rather than
+/// 7.000000000000001%)
+fn format_percent(percent: f64) -> String {
+    let percent = format!("{percent:.4}");
+    percent
+        .trim_end_matches('0')
+        .trim_end_matches('.')
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::reader::get_reader;
+    use std::path::Path;
+
+    fn sample(mode: SampleMode, seed: u64) -> Vec<Vec<u8>> {
+        let mut sampler = Sampler::new(mode, seed);
+        let mut ids = Vec::new();
+        let mut reader = get_reader(Path::new("test_inputs/reads.fastq.gz"));
+        while let Some(record) = reader.next_record() {
+            if sampler.sample(&record) {
+                ids.push(record.id().to_vec());
+            }
+        }
+
+        assert_eq!(sampler.sampling().total_reads, 1000);
+        assert_eq!(sampler.sampling().total_bases, 5957360);
+        ids.extend(sampler.into_reservoir().into_iter().map(|r| r.id));
+        ids
+    }
+
+    #[test]
+    fn test_sampler() {
+        let ids = sample(SampleMode::Fraction(0.1), 42);
+        assert!((70..130).contains(&ids.len()));
+        assert_eq!(ids, sample(SampleMode::Fraction(0.1), 42));
+        assert_ne!(ids, sample(SampleMode::Fraction(0.1), 7));
+
+        let ids = sample(SampleMode::Reads(100), 42);
+        assert_eq!(ids.len(), 100);
+        assert_eq!(ids, sample(SampleMode::Reads(100), 42));
+        assert_eq!(sample(SampleMode::Reads(5000), 42).len(), 1000);
+    }
+
+    #[test]
+    fn test_fraction_description() {
+        let sampler = Sampler::new(SampleMode::Fraction(0.07), 1);
+        assert_eq!(sampler.sampling().description, "7% of the reads (seed 1)");
+        assert_eq!(format_percent(0.0125 * 100.0), "1.25");
+        assert_eq!(format_percent(100.0 / 3.0), "33.3333");
+    }
+}
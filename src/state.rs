@@ -0,0 +1,164 @@
+use crate::metrics::MetricsBuilder;
+use crate::writer::create_output;
+use flate2::read::GzDecoder;
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::path::{Path, PathBuf};
+
+/// Saves the builders of a run as JSON, gzipped when the path ends with .gz
+pub fn write_state(path: &Path, builders: &[MetricsBuilder]) {
+    let mut output = create_output(path);
+    serde_json::to_writer(&mut output, builders)
+        .unwrap_or_else(|e| panic!("Failed to write state {path:?}: {e}"));
+    output.finish();
+}
+
+pub fn read_state(path: &Path) -> Vec<MetricsBuilder> {
+    let file = File::open(path).unwrap_or_else(|e| panic!("Failed to open {path:?}: {e}"));
+    let reader: Box<dyn Read> = if path.extension().is_some_and(|ext| ext == "gz") {
+        Box::new(GzDecoder::new(file))
+    } else {
+        Box::new(file)
+    };
+
+    serde_json::from_reader(BufReader::new(reader))
+        .unwrap_or_else(|e| panic!("Failed to read state {path:?}: {e}"))
+}
+
+/// Merges the states saved by runs over chunks of the same inputs. The n-th
+/// builder of each state is merged with the n-th builder of the others,
+/// which must have the same name and have been computed with the same options.
+pub fn merge_states(paths: &[PathBuf]) -> Result<Vec<MetricsBuilder>, String> {
+    let mut builders = read_state(&paths[0]);
+
+    for path in &paths[1..] {
+        let other_builders = read_state(path);
+        if other_builders.len() != builders.len() {
+            return Err(format!(
+                "{path:?} holds {} inputs instead of {}, states must hold the same inputs in the same order",
+                other_builders.len(),
+                builders.len()
+            ));
+        }
+
+        for (builder, other) in builders.iter_mut().zip(other_builders) {
+            check_chunks(builder, &other).map_err(|e| format!("Cannot merge {path:?}: {e}"))?;
+            builder.merge(other);
+        }
+    }
+
+    Ok(builders)
+}
+
+/// Checks that two builders hold chunks of the same input
+fn check_chunks(builder: &MetricsBuilder, other: &MetricsBuilder) -> Result<(), String> {
+    let name = &builder.filename;
+    if other.filename != *name {
+        return Err(format!(
+            "it holds {} where {name} is expected, states must hold the same inputs in the same order",
+            other.filename
+        ));
+    }
+    if other.qual_offset != builder.qual_offset {
+        return Err(format!(
+            "{name} was read with quality offsets {} and {}",
+            builder.qual_offset, other.qual_offset
+        ));
+    }
+    if other.genome_size != builder.genome_size {
+        return Err(format!(
+            "{name} was read with genome sizes {} and {}",
+            builder.genome_size, other.genome_size
+        ));
+    }
+    if other.protein != builder.protein {
+        return Err(format!("{name} was read with and without --protein"));
+    }
+    let options = |b: &MetricsBuilder| b.checksum.as_ref().map(|c| c.options);
+    if options(other) != options(builder) {
+        return Err(format!(
+            "the checksums of {name} were computed with different options"
+        ));
+    }
+    // The components of a graph split in chunks are unknown
+    if builder.components > 0 || other.components > 0 {
+        return Err(format!("{name} is a graph, whose chunks cannot be merged"));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::{compute_stats, Config};
+    use crate::reader::get_reader;
+
+    #[test]
+    fn test_merge_states() {
+        let path = PathBuf::from("test_inputs/reads.fastq.gz");
+
+        // Split the reads in two chunks, saved in two states
+        let mut chunks = [
+            MetricsBuilder::new("reads.fastq.gz", 0, None),
+            MetricsBuilder::new("reads_2.fastq.gz", 0, None),
+        ];
+        let mut reader = get_reader(&path);
+        let mut i = 0;
+        while let Some(record) = reader.next_record() {
+            chunks[i % 2].add_record(&record.seq(), record.qual());
+            i += 1;
+        }
+
+        // Both chunks keep the name of the input
+        chunks[1].filename = "reads".to_string();
+        let state_paths = temp_paths("merge", [".json", ".json.gz"]);
+        for (chunk, state_path) in chunks.into_iter().zip(&state_paths) {
+            write_state(state_path, &[chunk]);
+        }
+
+        let merged = merge_states(&state_paths).unwrap().pop().unwrap().compute();
+        let config = Config {
+            qual_offset: 33,
+            ..Default::default()
+        };
+        let expected = compute_stats(&[path], &config, &mut None, None);
+
+        assert_eq!(merged.filename, "reads");
+        assert_eq!(merged.number, expected.number);
+        assert_eq!(merged.cumul, expected.cumul);
+        assert_eq!(merged.n50, expected.n50);
+        assert_eq!(merged.l90, expected.l90);
+        assert_eq!(merged.number_gc, expected.number_gc);
+        assert_eq!(merged.mean_quality, expected.mean_quality);
+        for state_path in state_paths {
+            std::fs::remove_file(state_path).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_merge_mismatched_states() {
+        let mut builders = [
+            MetricsBuilder::new("reads.fastq.gz", 0, None),
+            MetricsBuilder::new("reads.fastq.gz", 0, None),
+        ];
+        builders[1].qual_offset = 64;
+        let state_paths = temp_paths("mismatch", [".json", ".json"]);
+        for (builder, state_path) in builders.into_iter().zip(&state_paths) {
+            write_state(state_path, &[builder]);
+        }
+
+        let error = merge_states(&state_paths).unwrap_err();
+        assert!(error.ends_with("reads was read with quality offsets 33 and 64"));
+        for state_path in state_paths {
+            std::fs::remove_file(state_path).unwrap();
+        }
+    }
+
+    /// Paths of temporary states, unique to the test and the test run
+    fn temp_paths(test: &str, extensions: [&str; 2]) -> [PathBuf; 2] {
+        let dir = std::env::temp_dir();
+        let id = std::process::id();
+        [1, 2].map(|i| dir.join(format!("fastoche_{test}_{id}_{i}{}", extensions[i - 1])))
+    }
+}
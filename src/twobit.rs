@@ -0,0 +1,159 @@
+use std::fs::File;
+use std::io::{BufReader, Read, Seek, SeekFrom};
+
+const SIGNATURE: u32 = 0x1A412743;
+const BASES: [u8; 4] = [b'T', b'C', b'A', b'G'];
+
+/// Sequence of a 2bit file, with its N blocks and soft-masked (lowercase)
+/// blocks restored
+#[derive(Debug, Default)]
+pub struct TwoBitRecord {
+    pub name: Vec<u8>,
+    pub seq: Vec<u8>,
+}
+
+/// Reads the sequences of a UCSC 2bit file, in the order of its index
+pub struct TwoBitReader {
+    reader: BufReader<File>,
+    big_endian: bool,
+    index: Vec<(Vec<u8>, u64)>,
+    next_sequence: usize,
+    record: TwoBitRecord,
+}
+
+impl TwoBitReader {
+    pub fn new(mut reader: BufReader<File>) -> Self {
+        let mut header = [0u8; 16];
+        reader
+            .read_exact(&mut header)
+            .unwrap_or_else(|e| panic!("Failed to read 2bit header: {e}"));
+
+        let big_endian = match u32::from_le_bytes(header[0..4].try_into().unwrap()) {
+            SIGNATURE => false,
+            s if s.swap_bytes() == SIGNATURE => true,
+            _ => panic!("File is not a valid 2bit file"),
+        };
+
+        let mut reader = TwoBitReader {
+            reader,
+            big_endian,
+            index: Vec::new(),
+            next_sequence: 0,
+            record: TwoBitRecord::default(),
+        };
+
+        let version = reader.u32_at(&header[4..8]);
+        let sequence_count = reader.u32_at(&header[8..12]);
+        assert!(version <= 1, "Unsupported 2bit version {version}");
+
+        for _ in 0..sequence_count {
+            let name_size = reader.read_bytes(1)[0] as usize;
+            let name = reader.read_bytes(name_size);
+            // Version 1 files use 64-bit offsets
+            let offset = if version == 1 {
+                reader.read_u64()
+            } else {
+                reader.read_u32() as u64
+            };
+            reader.index.push((name, offset));
+        }
+
+        reader
+    }
+
+    // Not an Iterator, as the record is reused for each sequence
+    pub fn next_record(&mut self) -> Option<&TwoBitRecord> {
+        let (name, offset) = self.index.get(self.next_sequence)?.clone();
+        self.next_sequence += 1;
+
+        self.reader
+            .seek(SeekFrom::Start(offset))
+            .unwrap_or_else(|e| panic!("Failed to read 2bit file: {e}"));
+
+        let dna_size = self.read_u32() as usize;
+        let n_blocks = self.read_blocks();
+        let mask_blocks = self.read_blocks();
+        let _reserved = self.read_u32();
+
+        let packed = self.read_bytes(dna_size.div_ceil(4));
+        let seq = &mut self.record.seq;
+        seq.clear();
+        seq.extend(
+            (0..dna_size).map(|i| BASES[((packed[i / 4] >> (6 - 2 * (i % 4))) & 3) as usize]),
+        );
+
+        for (start, size) in n_blocks {
+            seq[start..start + size].fill(b'N');
+        }
+        for (start, size) in mask_blocks {
+            seq[start..start + size].make_ascii_lowercase();
+        }
+
+        self.record.name = name;
+        Some(&self.record)
+    }
+
+    /// Reads a list of blocks, given as their starts followed by their sizes
+    fn read_blocks(&mut self) -> Vec<(usize, usize)> {
+        let count = self.read_u32() as usize;
+        let starts = (0..count)
+            .map(|_| self.read_u32() as usize)
+            .collect::<Vec<usize>>();
+        let sizes = (0..count)
+            .map(|_| self.read_u32() as usize)
+            .collect::<Vec<usize>>();
+        starts.into_iter().zip(sizes).collect()
+    }
+
+    fn read_bytes(&mut self, n: usize) -> Vec<u8> {
+        let mut bytes = vec![0u8; n];
+        self.reader
+            .read_exact(&mut bytes)
+            .unwrap_or_else(|e| panic!("Truncated 2bit file: {e}"));
+        bytes
+    }
+
+    fn read_u32(&mut self) -> u32 {
+        let bytes = self.read_bytes(4);
+        self.u32_at(&bytes)
+    }
+
+    fn read_u64(&mut self) -> u64 {
+        let bytes: [u8; 8] = self.read_bytes(8).try_into().unwrap();
+        if self.big_endian {
+            u64::from_be_bytes(bytes)
+        } else {
+            u64::from_le_bytes(bytes)
+        }
+    }
+
+    fn u32_at(&self, bytes: &[u8]) -> u32 {
+        let bytes: [u8; 4] = bytes.try_into().unwrap();
+        if self.big_endian {
+            u32::from_be_bytes(bytes)
+        } else {
+            u32::from_le_bytes(bytes)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_twobit_reader() {
+        let file = File::open("test_inputs/genome.2bit").unwrap();
+        let mut reader = TwoBitReader::new(BufReader::new(file));
+
+        let record = reader.next_record().unwrap();
+        assert_eq!(record.name, b"chr1");
+        assert_eq!(record.seq, b"ACGTACGTNNNNNNNNNNacgtacgtGGCCGGCCAT");
+
+        let record = reader.next_record().unwrap();
+        assert_eq!(record.name, b"chr2");
+        assert_eq!(record.seq, b"TTTTaaaaCCCCNNNNGGGGA");
+
+        assert!(reader.next_record().is_none());
+    }
+}
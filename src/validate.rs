@@ -0,0 +1,119 @@
+use crate::composition::non_nucleotide_message;
+use crate::inputs::Input;
+use crate::panic_message;
+use crate::parser::{new_builder, Config};
+use crate::reader::get_reader;
+use std::collections::HashSet;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+/// Records read from an input and the problems found in them
+#[derive(Debug)]
+pub struct Validation {
+    pub filename: String,
+    pub records: usize,
+    pub errors: Vec<String>,
+}
+
+impl Validation {
+    pub fn is_valid(&self) -> bool {
+        self.errors.is_empty()
+    }
+}
+
+/// Reads every record of an input, checking that the files can be parsed and
+/// that no sequence is empty, duplicated or holds unexpected characters
+pub fn validate(input: &Input, config: &Config) -> Validation {
+    let mut builder = new_builder(&input.paths[0], config, input.name.clone());
+    let mut errors = Vec::new();
+    let mut ids = HashSet::new();
+    let (mut empty, mut duplicated, mut low_qualities) = (0, 0, 0);
+
+    for path in &input.paths {
+        let read = catch_unwind(AssertUnwindSafe(|| {
+            let mut reader = get_reader(path);
+            reader.set_qual_offset(config.qual_offset);
+            while let Some(record) = reader.next_record() {
+                // Fastx ids hold the whole header line
+                let id = record.id().split(|c| c.is_ascii_whitespace()).next();
+                if !ids.insert(id.unwrap_or_default().to_vec()) {
+                    duplicated += 1;
+                }
+                if record.is_empty() {
+                    empty += 1;
+                }
+                if record
+                    .qual()
+                    .is_some_and(|qual| qual.iter().any(|q| *q < config.qual_offset))
+                {
+                    low_qualities += 1;
+                }
+                builder.add_record(&record.seq(), None);
+            }
+        }));
+        if let Err(panic) = read {
+            errors.push(format!(
+                "{path:?} could not be read: {}",
+                panic_message(panic)
+            ));
+        }
+    }
+
+    let records = builder.seq_sizes.len();
+    if records == 0 && errors.is_empty() {
+        errors.push("No records".to_string());
+    }
+    if empty > 0 {
+        errors.push(format!("{empty} empty sequences"));
+    }
+    if duplicated > 0 {
+        errors.push(format!("{duplicated} duplicated ids"));
+    }
+    if low_qualities > 0 {
+        errors.push(format!(
+            "{low_qualities} records with qualities below the offset ({}), check the quality offset",
+            config.qual_offset
+        ));
+    }
+
+    let filename = builder.filename.clone();
+    if records > 0 {
+        errors.extend(non_nucleotide_message(&builder.compute()));
+    }
+
+    Validation {
+        filename,
+        records,
+        errors,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::inputs::parse_input;
+
+    #[test]
+    fn test_validate() {
+        let config = Config {
+            qual_offset: 33,
+            ..Default::default()
+        };
+
+        let validation = validate(&parse_input("test_inputs/reads.fastq.gz").unwrap(), &config);
+        assert!(validation.is_valid());
+        assert_eq!(validation.records, 1000);
+
+        // The same records twice have duplicated ids
+        let input =
+            parse_input("twice=test_inputs/reads.fastq.gz,test_inputs/reads.fastq.gz").unwrap();
+        let validation = validate(&input, &config);
+        assert_eq!(validation.errors, ["1000 duplicated ids"]);
+
+        let validation = validate(&parse_input("test_inputs/missing.fa").unwrap(), &config);
+        assert_eq!(validation.records, 0);
+        assert_eq!(
+            validation.errors,
+            ["\"test_inputs/missing.fa\" could not be read: File not found \"test_inputs/missing.fa\""]
+        );
+    }
+}
@@ -0,0 +1,143 @@
+use crate::reader::Record;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use std::fs::File;
+use std::io::{self, BufWriter, Stdout, Write};
+use std::path::{Path, PathBuf};
+
+/// Output of the records, which must be finished to report the errors of
+/// the last writes: `BufWriter` and `GzEncoder` ignore them when dropped.
+pub struct Output {
+    name: String,
+    writer: OutputWriter,
+}
+
+enum OutputWriter {
+    Plain(BufWriter<File>),
+    Gzip(BufWriter<GzEncoder<File>>),
+    Stdout(BufWriter<Stdout>),
+}
+
+impl Output {
+    pub fn stdout() -> Self {
+        Output {
+            name: "stdout".to_string(),
+            writer: OutputWriter::Stdout(BufWriter::new(io::stdout())),
+        }
+    }
+
+    /// Flushes the output and writes the gzip trailer
+    pub fn finish(self) {
+        let name = self.name;
+        let finished = match self.writer {
+            OutputWriter::Plain(mut writer) => writer.flush(),
+            OutputWriter::Gzip(writer) => writer
+                .into_inner()
+                .map_err(|e| e.into_error())
+                .and_then(|encoder| encoder.finish().map(|_| ())),
+            OutputWriter::Stdout(mut writer) => writer.flush(),
+        };
+        finished.unwrap_or_else(|e| panic!("Failed to write {name}: {e}"));
+    }
+}
+
+impl Write for Output {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match &mut self.writer {
+            OutputWriter::Plain(writer) => writer.write(buf),
+            OutputWriter::Gzip(writer) => writer.write(buf),
+            OutputWriter::Stdout(writer) => writer.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match &mut self.writer {
+            OutputWriter::Plain(writer) => writer.flush(),
+            OutputWriter::Gzip(writer) => writer.flush(),
+            OutputWriter::Stdout(writer) => writer.flush(),
+        }
+    }
+}
+
+/// Creates an output file, gzipped when its path ends with .gz
+pub fn create_output(path: &Path) -> Output {
+    let file = File::create(path).unwrap_or_else(|e| panic!("Failed to create {path:?}: {e}"));
+    let writer = if path.extension().is_some_and(|ext| ext == "gz") {
+        OutputWriter::Gzip(BufWriter::new(GzEncoder::new(file, Compression::default())))
+    } else {
+        OutputWriter::Plain(BufWriter::new(file))
+    };
+
+    Output {
+        name: format!("{:?}", PathBuf::from(path)),
+        writer,
+    }
+}
+
+/// Writes a record as Fastq when it has qualities, as Fasta otherwise
+pub fn write_record<W: Write + ?Sized>(w: &mut W, record: &Record) -> io::Result<()> {
+    match record.qual() {
+        Some(qual) => {
+            w.write_all(b"@")?;
+            w.write_all(record.id())?;
+            w.write_all(b"\n")?;
+            w.write_all(&record.seq())?;
+            w.write_all(b"\n+\n")?;
+            w.write_all(qual)?;
+        }
+        None => {
+            w.write_all(b">")?;
+            w.write_all(record.id())?;
+            w.write_all(b"\n")?;
+            w.write_all(&record.seq())?;
+        }
+    }
+    w.write_all(b"\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::reader::get_reader;
+    use std::path::Path;
+
+    #[test]
+    fn test_write_record() {
+        let mut reader = get_reader(Path::new("test_inputs/reads.fastq.gz"));
+        let record = reader.next_record().unwrap();
+        let mut fastq = Vec::new();
+        write_record(&mut fastq, &record).unwrap();
+
+        let lines = fastq.split(|c| *c == b'\n').collect::<Vec<&[u8]>>();
+        assert_eq!(lines.len(), 5);
+        assert_eq!(lines[0][0], b'@');
+        assert_eq!(&lines[0][1..], record.id());
+        assert_eq!(lines[1], &*record.seq());
+        assert_eq!(lines[3], record.qual().unwrap());
+
+        let mut reader = get_reader(Path::new("test_inputs/genome.2bit"));
+        let mut fasta = Vec::new();
+        write_record(&mut fasta, &reader.next_record().unwrap()).unwrap();
+        assert_eq!(fasta, b">chr1\nACGTACGTNNNNNNNNNNacgtacgtGGCCGGCCAT\n");
+    }
+
+    #[test]
+    fn test_gzipped_output() {
+        let path = std::env::temp_dir().join(format!("fastoche_{}.fa.gz", std::process::id()));
+        let mut output = create_output(&path);
+        let mut reader = get_reader(Path::new("test_inputs/genome.2bit"));
+        while let Some(record) = reader.next_record() {
+            write_record(&mut output, &record).unwrap();
+        }
+        output.finish();
+
+        let mut reader = get_reader(&path);
+        assert_eq!(reader.next_record().unwrap().id(), b"chr1");
+        assert_eq!(
+            &*reader.next_record().unwrap().seq(),
+            b"TTTTaaaaCCCCNNNNGGGGA"
+        );
+        assert!(reader.next_record().is_none());
+        std::fs::remove_file(path).unwrap();
+    }
+}